@@ -0,0 +1,310 @@
+use anyhow::{anyhow, Result};
+use cpal::traits::DeviceTrait;
+use cpal::{
+    BufferSize, SampleFormat, SampleRate, StreamConfig, SupportedBufferSize, SupportedStreamConfig,
+    SupportedStreamConfigRange,
+};
+use serde::{Deserialize, Serialize};
+
+/// Rates offered in the settings UI when a device supports a continuous range.
+const COMMON_SAMPLE_RATES: [u32; 11] = [
+    8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000,
+];
+
+/// Per-device capture format, stored in `AppSettings::device_configs`.
+/// Unset fields keep the device default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub sample_rate: Option<u32>,
+    /// Frames per callback.
+    pub buffer_size: Option<u32>,
+    /// Zero-based channels to record. All channels are averaged when empty;
+    /// otherwise only these are kept, so a vocal mic on one input of a
+    /// multi-channel interface is not mixed with the others.
+    pub channels: Vec<u16>,
+}
+
+/// Stream parameters resolved from a [`CaptureConfig`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedCapture {
+    pub sample_format: SampleFormat,
+    pub stream_config: StreamConfig,
+    /// Device channel index for each channel handed to the consumer.
+    pub channel_map: Vec<usize>,
+}
+
+impl ResolvedCapture {
+    /// The device default with every channel kept.
+    pub fn from_default(config: SupportedStreamConfig) -> Self {
+        let channel_map = (0..config.channels() as usize).collect();
+        Self {
+            sample_format: config.sample_format(),
+            stream_config: config.into(),
+            channel_map,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.stream_config.sample_rate.0
+    }
+
+    /// Channels after selection, as seen by the resampler and VAD.
+    pub fn channels(&self) -> u16 {
+        self.channel_map.len() as u16
+    }
+}
+
+/// What a device supports, for the per-device settings UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceCapabilities {
+    pub default_sample_rate: u32,
+    pub default_channels: u16,
+    pub max_channels: u16,
+    pub sample_rates: Vec<u32>,
+    pub min_buffer_size: Option<u32>,
+    pub max_buffer_size: Option<u32>,
+}
+
+/// Resolve `config` for `device`, validating it against the device's
+/// supported input configurations.
+pub fn resolve(device: &cpal::Device, config: &CaptureConfig) -> Result<ResolvedCapture> {
+    let default = device.default_input_config()?;
+    if *config == CaptureConfig::default() {
+        return Ok(ResolvedCapture::from_default(default));
+    }
+    let ranges: Vec<_> = device.supported_input_configs()?.collect();
+    select(&default, &ranges, config)
+}
+
+/// Check `config` against the named device. Devices that are not connected
+/// cannot be checked and are accepted as-is.
+pub fn validate(device_name: &str, config: &CaptureConfig) -> Result<()> {
+    match super::find_named_device(device_name) {
+        Some(device) => resolve(&device, config).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+pub fn capabilities(device_name: &Option<String>) -> Result<DeviceCapabilities> {
+    let device = super::find_device(device_name)?;
+    let default = device.default_input_config()?;
+    let ranges: Vec<_> = device.supported_input_configs()?.collect();
+
+    let mut sample_rates: Vec<u32> = COMMON_SAMPLE_RATES
+        .iter()
+        .copied()
+        .chain(ranges.iter().map(|r| r.min_sample_rate().0))
+        .chain(ranges.iter().map(|r| r.max_sample_rate().0))
+        .filter(|&rate| ranges.iter().any(|r| supports_rate(r, rate)))
+        .collect();
+    sample_rates.sort_unstable();
+    sample_rates.dedup();
+
+    let buffer_ranges = ranges.iter().filter_map(|r| match r.buffer_size() {
+        SupportedBufferSize::Range { min, max } => Some((*min, *max)),
+        SupportedBufferSize::Unknown => None,
+    });
+    let (min_buffer_size, max_buffer_size) =
+        buffer_ranges.fold((None, None), |(lo, hi), (min, max)| {
+            (
+                Some(lo.map_or(min, |lo: u32| lo.min(min))),
+                Some(hi.map_or(max, |hi: u32| hi.max(max))),
+            )
+        });
+
+    Ok(DeviceCapabilities {
+        default_sample_rate: default.sample_rate().0,
+        default_channels: default.channels(),
+        max_channels: ranges
+            .iter()
+            .map(|r| r.channels())
+            .max()
+            .unwrap_or(default.channels()),
+        sample_rates,
+        min_buffer_size,
+        max_buffer_size,
+    })
+}
+
+fn supports_rate(range: &SupportedStreamConfigRange, rate: u32) -> bool {
+    range.min_sample_rate().0 <= rate && rate <= range.max_sample_rate().0
+}
+
+/// Pick the supported range that satisfies `config`, preferring the
+/// default sample format and the fewest channels that cover the selection.
+fn select(
+    default: &SupportedStreamConfig,
+    ranges: &[SupportedStreamConfigRange],
+    config: &CaptureConfig,
+) -> Result<ResolvedCapture> {
+    let max_channels = ranges.iter().map(|r| r.channels()).max().unwrap_or(0);
+
+    let mut selected: Vec<u16> = Vec::new();
+    for &channel in &config.channels {
+        if channel >= max_channels {
+            return Err(anyhow!(
+                "Channel {} is out of range; the device has {} channels",
+                channel + 1,
+                max_channels
+            ));
+        }
+        if !selected.contains(&channel) {
+            selected.push(channel);
+        }
+    }
+
+    let rate = config.sample_rate.unwrap_or(default.sample_rate().0);
+    if !ranges.iter().any(|r| supports_rate(r, rate)) {
+        return Err(anyhow!(
+            "Sample rate {}Hz is not supported by this device",
+            rate
+        ));
+    }
+
+    // With no selection keep the default channel count, as before
+    let channel_ok = |r: &SupportedStreamConfigRange| match selected.iter().max() {
+        Some(&highest) => r.channels() > highest,
+        None => r.channels() == default.channels(),
+    };
+    let range = ranges
+        .iter()
+        .filter(|r| channel_ok(r) && supports_rate(r, rate))
+        .min_by_key(|r| (r.sample_format() != default.sample_format(), r.channels()))
+        .ok_or_else(|| {
+            anyhow!(
+                "The device does not support {}Hz with the selected channels",
+                rate
+            )
+        })?;
+
+    let buffer_size = match (config.buffer_size, range.buffer_size()) {
+        (None, _) => BufferSize::Default,
+        (Some(frames), SupportedBufferSize::Range { min, max }) => {
+            if frames < *min || frames > *max {
+                return Err(anyhow!(
+                    "Buffer size {} is outside the supported range {}-{}",
+                    frames,
+                    min,
+                    max
+                ));
+            }
+            BufferSize::Fixed(frames)
+        }
+        // The host cannot report limits; let it reject the size if invalid
+        (Some(frames), SupportedBufferSize::Unknown) => BufferSize::Fixed(frames),
+    };
+
+    let channel_map = if selected.is_empty() {
+        (0..range.channels() as usize).collect()
+    } else {
+        selected.iter().map(|&c| c as usize).collect()
+    };
+
+    Ok(ResolvedCapture {
+        sample_format: range.sample_format(),
+        stream_config: StreamConfig {
+            channels: range.channels(),
+            sample_rate: SampleRate(rate),
+            buffer_size,
+        },
+        channel_map,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(
+        channels: u16,
+        min: u32,
+        max: u32,
+        format: SampleFormat,
+    ) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(min),
+            SampleRate(max),
+            SupportedBufferSize::Range { min: 64, max: 4096 },
+            format,
+        )
+    }
+
+    fn default_config() -> SupportedStreamConfig {
+        SupportedStreamConfig::new(
+            2,
+            SampleRate(48000),
+            SupportedBufferSize::Range { min: 64, max: 4096 },
+            SampleFormat::F32,
+        )
+    }
+
+    fn interface_ranges() -> Vec<SupportedStreamConfigRange> {
+        vec![
+            range(2, 44100, 48000, SampleFormat::I16),
+            range(2, 44100, 48000, SampleFormat::F32),
+            range(4, 44100, 96000, SampleFormat::F32),
+        ]
+    }
+
+    #[test]
+    fn test_default_config_keeps_all_channels() {
+        let resolved = select(
+            &default_config(),
+            &interface_ranges(),
+            &CaptureConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(resolved.stream_config.channels, 2);
+        assert_eq!(resolved.sample_format, SampleFormat::F32);
+        assert_eq!(resolved.channel_map, vec![0, 1]);
+        assert_eq!(resolved.stream_config.buffer_size, BufferSize::Default);
+    }
+
+    #[test]
+    fn test_channel_selection_opens_enough_channels() {
+        let config = CaptureConfig {
+            channels: vec![2, 2],
+            ..Default::default()
+        };
+        let resolved = select(&default_config(), &interface_ranges(), &config).unwrap();
+        assert_eq!(resolved.stream_config.channels, 4);
+        assert_eq!(resolved.channel_map, vec![2]);
+        assert_eq!(resolved.channels(), 1);
+    }
+
+    #[test]
+    fn test_sample_rate_and_buffer_size_are_validated() {
+        let config = CaptureConfig {
+            sample_rate: Some(96000),
+            buffer_size: Some(256),
+            channels: vec![0],
+        };
+        let resolved = select(&default_config(), &interface_ranges(), &config).unwrap();
+        assert_eq!(resolved.sample_rate(), 96000);
+        assert_eq!(resolved.stream_config.buffer_size, BufferSize::Fixed(256));
+
+        let unsupported_rate = CaptureConfig {
+            sample_rate: Some(8000),
+            ..Default::default()
+        };
+        assert!(select(&default_config(), &interface_ranges(), &unsupported_rate).is_err());
+
+        let oversized_buffer = CaptureConfig {
+            buffer_size: Some(8192),
+            ..Default::default()
+        };
+        assert!(select(&default_config(), &interface_ranges(), &oversized_buffer).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_channel_is_rejected() {
+        let config = CaptureConfig {
+            channels: vec![4],
+            ..Default::default()
+        };
+        let err = select(&default_config(), &interface_ranges(), &config).unwrap_err();
+        assert!(err.to_string().contains("Channel 5"));
+    }
+}
@@ -0,0 +1,378 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::resampler::{ResampleQuality, StreamingResampler};
+
+/// Decode an audio file to 16kHz mono, the same format recordings are
+/// transcribed from. The file is resampled as it is decoded, so long files
+/// are never held at their original rate.
+pub fn decode_file(path: &Path, quality: ResampleQuality) -> Result<Vec<f32>> {
+    let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| anyhow!("Unsupported audio file: {}", e))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("No audio track in {}", path.display()))?;
+    // Symphonia reads the Ogg container but has no Opus decoder
+    if track.codec_params.codec == CODEC_TYPE_OPUS {
+        return decode_ogg_opus(path);
+    }
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow!("Unsupported audio codec: {}", e))?;
+
+    let mut output = Vec::new();
+    let mut resampler: Option<(StreamingResampler, u32, usize)> = None;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(anyhow!("Failed to read audio: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet loses a few milliseconds, not the file
+            Err(DecodeError::DecodeError(e)) => {
+                log::warn!("Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(anyhow!("Failed to decode audio: {}", e)),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        if buffer
+            .as_ref()
+            .is_none_or(|b| b.capacity() < decoded.capacity() * channels)
+        {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        let buffer = buffer.as_mut().unwrap();
+        buffer.copy_interleaved_ref(decoded);
+
+        // Rate or layout may change between chained streams
+        if resampler
+            .as_ref()
+            .is_none_or(|&(_, rate, ch)| rate != spec.rate || ch != channels)
+        {
+            if let Some((mut previous, _, _)) = resampler.take() {
+                previous.finish(&mut output);
+            }
+            let new = StreamingResampler::new(spec.rate, channels as u16, quality);
+            resampler = Some((new, spec.rate, channels));
+        }
+        let (resampler, _, _) = resampler.as_mut().unwrap();
+        resampler.push(buffer.samples(), &mut output);
+    }
+    if let Some((mut resampler, _, _)) = resampler {
+        resampler.finish(&mut output);
+    }
+    Ok(output)
+}
+
+/// Decode an Ogg/Opus file to 16kHz mono. libopus resamples and downmixes
+/// itself, so the output needs no further conversion.
+#[cfg(feature = "ogg-opus")]
+fn decode_ogg_opus(path: &Path) -> Result<Vec<f32>> {
+    /// Granule positions and pre-skip count 48kHz samples.
+    const GRANULE_SCALE: u64 = 48000 / 16000;
+    /// Longest Opus packet is 120ms.
+    const MAX_PACKET_LEN: usize = 16000 * 120 / 1000;
+
+    let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let mut reader = ogg::PacketReader::new(std::io::BufReader::new(file));
+    let head = reader
+        .read_packet_expected()
+        .map_err(|e| anyhow!("Failed to read audio: {}", e))?;
+    if head.data.len() < 19 || !head.data.starts_with(b"OpusHead") {
+        return Err(anyhow!("Not an Ogg/Opus file: {}", path.display()));
+    }
+    // Mapping family 0 is mono or stereo; others need a multistream decoder
+    if head.data[18] != 0 {
+        return Err(anyhow!(
+            "Ogg/Opus with more than two channels is not supported"
+        ));
+    }
+    let serial = head.stream_serial();
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as u64 / GRANULE_SCALE;
+
+    let mut decoder = opus::Decoder::new(16000, opus::Channels::Mono)?;
+    let mut frame = vec![0.0f32; MAX_PACKET_LEN];
+    let mut output = Vec::new();
+    let mut skip = pre_skip as usize;
+    let mut tags_read = false;
+    while let Some(packet) = reader
+        .read_packet()
+        .map_err(|e| anyhow!("Failed to read audio: {}", e))?
+    {
+        if packet.stream_serial() != serial {
+            continue;
+        }
+        // The second packet is OpusTags
+        if !tags_read {
+            tags_read = true;
+            continue;
+        }
+
+        let len = match decoder.decode_float(&packet.data, &mut frame, false) {
+            Ok(len) => len,
+            // A corrupt packet loses a few milliseconds, not the file
+            Err(e) => {
+                log::warn!("Skipping undecodable packet: {}", e);
+                continue;
+            }
+        };
+        output.extend_from_slice(&frame[len.min(skip)..len]);
+        skip = skip.saturating_sub(len);
+
+        if packet.last_in_stream() {
+            // The last granule position marks where the audio really ends
+            let end = (packet.absgp_page() / GRANULE_SCALE).saturating_sub(pre_skip);
+            output.truncate(end as usize);
+            break;
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(not(feature = "ogg-opus"))]
+fn decode_ogg_opus(_path: &Path) -> Result<Vec<f32>> {
+    Err(anyhow!(
+        "Ogg/Opus needs the ogg-opus feature. Rebuild with --features ogg-opus (requires CMake)."
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_stereo_wav_to_16khz_mono() {
+        let path = std::env::temp_dir().join(format!("decode-{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..44100 {
+            let sample = (8000.0 * (i as f32 * 0.05).sin()) as i16;
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let audio = decode_file(&path, ResampleQuality::Balanced).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!((audio.len() as i64 - 16000).abs() <= 1);
+        let peak = audio.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 8000.0 / 32768.0).abs() < 0.02);
+    }
+
+    /// Vorbis packs header fields least significant bit first.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, bits: usize) {
+            for i in 0..bits {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                if value >> i & 1 != 0 {
+                    *self.bytes.last_mut().unwrap() |= 1 << (self.bits % 8);
+                }
+                self.bits += 1;
+            }
+        }
+    }
+
+    fn ogg_page(flags: u8, granule: u64, sequence: u32, packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.extend_from_slice(&[0, flags]);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&1u32.to_le_bytes()); // serial
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]); // checksum, filled in below
+        let lacing: Vec<u8> = packets
+            .iter()
+            .flat_map(|p| {
+                let mut lacing = vec![255u8; p.len() / 255];
+                lacing.push((p.len() % 255) as u8);
+                lacing
+            })
+            .collect();
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        packets.iter().for_each(|p| page.extend_from_slice(p));
+
+        let mut crc = 0u32;
+        for &byte in &page {
+            crc ^= (byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04c1_1db7
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    /// The smallest valid Ogg/Vorbis stream: one second of silence at
+    /// 32kHz, in short blocks whose floors are all marked unused.
+    fn silent_ogg_vorbis() -> Vec<u8> {
+        const RATE: u32 = 32000;
+        const HALF_BLOCK: u32 = 128;
+
+        let mut ident = b"\x01vorbis".to_vec();
+        ident.extend_from_slice(&0u32.to_le_bytes()); // version
+        ident.push(1); // channels
+        ident.extend_from_slice(&RATE.to_le_bytes());
+        ident.extend_from_slice(&[0; 12]); // bitrates
+        ident.push(0x88); // both block sizes 256
+        ident.push(1); // framing
+
+        let mut comment = b"\x03vorbis".to_vec();
+        comment.extend_from_slice(&[0; 8]); // vendor, comment count
+        comment.push(1); // framing
+
+        let mut setup = BitWriter::default();
+        setup.write(0, 8); // one codebook: one dimension, two 1-bit entries
+        setup.write(0x564342, 24);
+        setup.write(1, 16);
+        setup.write(2, 24);
+        setup.write(0, 2); // not ordered, not sparse
+        setup.write(0, 5);
+        setup.write(0, 5);
+        setup.write(0, 4); // no lookup
+        setup.write(0, 6); // one time transform placeholder
+        setup.write(0, 16);
+        setup.write(0, 6); // one floor 1 without partitions
+        setup.write(1, 16);
+        setup.write(0, 5);
+        setup.write(1, 2);
+        setup.write(8, 4);
+        setup.write(0, 6); // one empty residue 0
+        setup.write(0, 16);
+        setup.write(0, 24);
+        setup.write(0, 24);
+        setup.write(0, 24);
+        setup.write(0, 6);
+        setup.write(0, 8);
+        setup.write(0, 4);
+        setup.write(0, 6); // one mapping
+        setup.write(0, 16);
+        setup.write(0, 4); // single submap, no coupling, reserved
+        setup.write(0, 24);
+        setup.write(0, 6); // one short-block mode
+        setup.write(0, 1);
+        setup.write(0, 16);
+        setup.write(0, 16);
+        setup.write(0, 8);
+        setup.write(1, 1); // framing
+        let mut setup_packet = b"\x05vorbis".to_vec();
+        setup_packet.extend_from_slice(&setup.bytes);
+
+        // An audio packet is a zero type bit and an unused floor
+        let blocks = RATE / HALF_BLOCK;
+        let audio = vec![vec![0u8]; blocks as usize + 1];
+
+        let mut stream = ogg_page(0x02, 0, 0, &[ident]);
+        stream.extend(ogg_page(0, 0, 1, &[comment, setup_packet]));
+        stream.extend(ogg_page(0x04, (blocks * HALF_BLOCK) as u64, 2, &audio));
+        stream
+    }
+
+    #[test]
+    fn test_decodes_ogg_vorbis() {
+        let path = std::env::temp_dir().join(format!("decode-{}.ogg", uuid::Uuid::new_v4()));
+        std::fs::write(&path, silent_ogg_vorbis()).unwrap();
+        let audio = decode_file(&path, ResampleQuality::Balanced);
+        std::fs::remove_file(&path).unwrap();
+        let audio = audio.unwrap();
+        assert!((audio.len() as i64 - 16000).abs() <= 128);
+        assert!(audio.iter().all(|s| s.abs() < 1e-6));
+    }
+
+    #[cfg(feature = "ogg-opus")]
+    #[test]
+    fn test_decodes_ogg_opus() {
+        let tone: Vec<f32> = (0..16000).map(|i| 0.3 * (i as f32 * 0.1).sin()).collect();
+        let ogg = crate::audio::encode::encode_ogg_opus(&tone, 16000).unwrap();
+        let path = std::env::temp_dir().join(format!("decode-{}.opus", uuid::Uuid::new_v4()));
+        std::fs::write(&path, ogg).unwrap();
+        let audio = decode_file(&path, ResampleQuality::Fast);
+        std::fs::remove_file(&path).unwrap();
+        let audio = audio.unwrap();
+        assert_eq!(audio.len(), 16000);
+        let peak = audio.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.2 && peak < 0.4);
+    }
+
+    #[cfg(not(feature = "ogg-opus"))]
+    #[test]
+    fn test_ogg_opus_needs_feature() {
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 1, 0, 0]); // version, channels, pre-skip
+        head.extend_from_slice(&16000u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]); // gain, mapping family
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&[0; 8]);
+
+        let path = std::env::temp_dir().join(format!("decode-{}.ogg", uuid::Uuid::new_v4()));
+        let mut stream = ogg_page(0x02, 0, 0, &[head]);
+        stream.extend(ogg_page(0, 0, 1, &[tags]));
+        stream.extend(ogg_page(0x04, 960, 2, &[vec![0xf8]]));
+        std::fs::write(&path, stream).unwrap();
+        let result = decode_file(&path, ResampleQuality::Fast);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("needs the ogg-opus feature"));
+    }
+
+    #[test]
+    fn test_rejects_non_audio() {
+        let path = std::env::temp_dir().join(format!("decode-{}.mp3", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"not audio at all").unwrap();
+        let result = decode_file(&path, ResampleQuality::Fast);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
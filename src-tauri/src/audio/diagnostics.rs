@@ -0,0 +1,267 @@
+use anyhow::Result;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use ringbuf::traits::{Consumer, Split};
+use ringbuf::HeapRb;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::capture::ResolvedCapture;
+use super::level::SharedLevel;
+use super::resampler::downmix_to_mono;
+use super::{build_stream, find_device, CallbackShared, OverrunCounter};
+
+/// Analysis frame length for the noise floor and speech level estimates.
+const FRAME_MS: usize = 20;
+
+/// Levels are floored here so digital silence does not produce -inf.
+const SILENCE_DB: f32 = -120.0;
+
+/// Samples at or above this magnitude count as clipped.
+const CLIP_THRESHOLD: f32 = 0.999;
+
+/// Runs of exact zeros at least this long inside the signal are dropouts.
+const DROPOUT_MIN_MS: usize = 5;
+
+/// Result of [`test_input_device`], returned to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceTestReport {
+    pub device_name: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+    pub duration_ms: u64,
+    pub noise_floor_db: f32,
+    pub peak_db: f32,
+    pub clipped_samples: u64,
+    pub snr_db: f32,
+    pub dropouts: u32,
+    pub suitable: bool,
+    pub recommendation: String,
+}
+
+/// Signal measurements over a captured test recording.
+#[derive(Debug, Clone, PartialEq)]
+struct SignalAnalysis {
+    /// Level of the quietest frames, taken as background noise.
+    noise_floor_db: f32,
+    /// Level of the loudest frames, taken as speech.
+    speech_db: f32,
+    peak_db: f32,
+    clipped_samples: u64,
+    dropouts: u32,
+}
+
+impl SignalAnalysis {
+    fn snr_db(&self) -> f32 {
+        self.speech_db - self.noise_floor_db
+    }
+}
+
+/// Record `duration` from the given device in its native format and report
+/// what it is capable of and whether it is suitable for dictation. Blocks
+/// for the duration of the test.
+pub fn test_input_device(
+    device_name: &Option<String>,
+    duration: Duration,
+) -> Result<DeviceTestReport> {
+    let device = find_device(device_name)?;
+    let name = device.name().unwrap_or_else(|_| "Unknown device".into());
+    let capture = ResolvedCapture::from_default(device.default_input_config()?);
+    let sample_rate = capture.sample_rate();
+    let channels = capture.channels();
+    let sample_format = capture.sample_format.to_string();
+
+    // Sized for the whole test so nothing has to be drained while it runs
+    let capacity =
+        (sample_rate as usize * channels as usize) * (duration.as_millis() as usize + 1000) / 1000;
+    let (producer, mut consumer) = HeapRb::<f32>::new(capacity).split();
+    let overruns = Arc::new(OverrunCounter::default());
+    let shared = CallbackShared {
+        paused: Arc::new(AtomicBool::new(false)),
+        overruns: overruns.clone(),
+        level: Arc::new(SharedLevel::default()),
+        fault: Arc::new(Mutex::new(None)),
+    };
+
+    let stream = build_stream(&device, &capture, producer, shared)?;
+    stream.play()?;
+    std::thread::sleep(duration);
+    drop(stream);
+
+    let samples: Vec<f32> = consumer.pop_iter().collect();
+    let mut analysis = analyze(&samples, sample_rate, channels);
+    analysis.dropouts += overruns.events.load(Ordering::Relaxed) as u32;
+    let (suitable, recommendation) = recommend(&analysis);
+
+    log::info!(
+        "Tested '{}': noise floor {:.1} dB, peak {:.1} dB, SNR {:.1} dB, {} dropouts",
+        name,
+        analysis.noise_floor_db,
+        analysis.peak_db,
+        analysis.snr_db(),
+        analysis.dropouts
+    );
+
+    Ok(DeviceTestReport {
+        device_name: name,
+        sample_rate,
+        channels,
+        sample_format,
+        duration_ms: samples.len() as u64 * 1000 / (sample_rate as u64 * channels as u64),
+        noise_floor_db: analysis.noise_floor_db,
+        peak_db: analysis.peak_db,
+        clipped_samples: analysis.clipped_samples,
+        snr_db: analysis.snr_db(),
+        dropouts: analysis.dropouts,
+        suitable,
+        recommendation,
+    })
+}
+
+fn to_db(level: f32) -> f32 {
+    if level <= 0.0 {
+        SILENCE_DB
+    } else {
+        (20.0 * level.log10()).max(SILENCE_DB)
+    }
+}
+
+/// Measure interleaved device-rate samples.
+fn analyze(samples: &[f32], sample_rate: u32, channels: u16) -> SignalAnalysis {
+    let clipped_samples = samples.iter().filter(|s| s.abs() >= CLIP_THRESHOLD).count() as u64;
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+    let mono = downmix_to_mono(samples, channels);
+
+    let frame_len = (sample_rate as usize * FRAME_MS / 1000).max(1);
+    let mut frame_db: Vec<f32> = mono
+        .chunks_exact(frame_len)
+        .map(|frame| to_db(super::rms(frame)))
+        .collect();
+    frame_db.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: f32| {
+        if frame_db.is_empty() {
+            SILENCE_DB
+        } else {
+            frame_db[((frame_db.len() - 1) as f32 * p).round() as usize]
+        }
+    };
+
+    // Zero runs before the first non-zero sample are stream start-up, not
+    // dropouts; a fully muted device is reported as having no signal.
+    let dropout_len = sample_rate as usize * DROPOUT_MIN_MS / 1000;
+    let mut dropouts = 0;
+    let mut zero_run = 0;
+    let mut seen_signal = false;
+    for &sample in &mono {
+        if sample == 0.0 {
+            zero_run += 1;
+            continue;
+        }
+        if seen_signal && zero_run >= dropout_len {
+            dropouts += 1;
+        }
+        seen_signal = true;
+        zero_run = 0;
+    }
+
+    SignalAnalysis {
+        noise_floor_db: percentile(0.1),
+        speech_db: percentile(0.95),
+        peak_db: to_db(peak),
+        clipped_samples,
+        dropouts,
+    }
+}
+
+/// Decide whether the device is usable and say what to fix if it is not.
+fn recommend(analysis: &SignalAnalysis) -> (bool, String) {
+    if analysis.peak_db < -50.0 {
+        return (
+            false,
+            "No signal detected. Check that the microphone is not muted and that this is the right input device.".into(),
+        );
+    }
+    if analysis.dropouts > 0 {
+        return (
+            false,
+            format!(
+                "{} dropouts detected. The device or its driver is losing audio; try another USB port or a different device.",
+                analysis.dropouts
+            ),
+        );
+    }
+    if analysis.clipped_samples > 0 {
+        return (
+            false,
+            "The input is clipping. Lower the input gain or move further from the microphone."
+                .into(),
+        );
+    }
+    if analysis.snr_db() < 15.0 {
+        return (
+            false,
+            "Speech barely rises above the background noise. Speak during the test, move closer or raise the input gain.".into(),
+        );
+    }
+    if analysis.noise_floor_db > -45.0 {
+        return (
+            true,
+            "Usable, but the background is noisy. A quieter room or a headset microphone will improve accuracy.".into(),
+        );
+    }
+    (true, "The device is well suited for dictation.".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1s of low-level noise followed by 1s of a loud tone, at 16kHz mono.
+    fn noise_then_tone(noise: f32, tone: f32) -> Vec<f32> {
+        let noise = (0..16000).map(|i| if i % 2 == 0 { noise } else { -noise });
+        let tone = (0..16000).map(|i| tone * (i as f32 * 0.1).sin());
+        noise.chain(tone).collect()
+    }
+
+    #[test]
+    fn test_estimates_noise_floor_and_snr() {
+        let analysis = analyze(&noise_then_tone(0.001, 0.5), 16000, 1);
+        assert!((analysis.noise_floor_db + 60.0).abs() < 1.0);
+        assert!(analysis.snr_db() > 45.0);
+        assert_eq!(analysis.dropouts, 0);
+        assert!(recommend(&analysis).0);
+    }
+
+    #[test]
+    fn test_muted_device_has_no_signal() {
+        let analysis = analyze(&vec![0.0; 32000], 16000, 2);
+        assert_eq!(analysis.peak_db, SILENCE_DB);
+        assert_eq!(analysis.dropouts, 0);
+        let (suitable, recommendation) = recommend(&analysis);
+        assert!(!suitable);
+        assert!(recommendation.starts_with("No signal"));
+    }
+
+    #[test]
+    fn test_counts_dropouts_inside_the_signal() {
+        let mut samples = noise_then_tone(0.01, 0.5);
+        // 10ms gap in the middle of the tone, plus leading start-up silence
+        samples[20000..20160].fill(0.0);
+        samples[..400].fill(0.0);
+        let analysis = analyze(&samples, 16000, 1);
+        assert_eq!(analysis.dropouts, 1);
+        assert!(!recommend(&analysis).0);
+    }
+
+    #[test]
+    fn test_detects_clipping() {
+        let analysis = analyze(&noise_then_tone(0.001, 1.5), 16000, 1);
+        assert!(analysis.clipped_samples > 0);
+        let (suitable, recommendation) = recommend(&analysis);
+        assert!(!suitable);
+        assert!(recommendation.contains("clipping"));
+    }
+}
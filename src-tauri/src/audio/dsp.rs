@@ -0,0 +1,410 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use super::rms;
+
+/// Rate of the audio the chain runs on.
+const DSP_RATE: u32 = 16000;
+
+/// STFT frame for noise suppression: 32ms, hopped by half.
+const FFT_LEN: usize = 512;
+const HOP_LEN: usize = FFT_LEN / 2;
+
+/// Share of the quietest frames averaged into the noise estimate.
+const NOISE_PERCENTILE: f32 = 0.2;
+
+/// Weight of earlier frames in the per-bin power the gain is based on.
+const POWER_SMOOTHING: f32 = 0.7;
+
+/// Lowest gain a bin is suppressed to. A floor keeps residual noise
+/// smooth instead of leaving isolated "musical" tones.
+const SUPPRESSION_FLOOR: f32 = 0.1;
+
+/// Analysis frame and time constant of the gain control.
+const AGC_FRAME_MS: u32 = 20;
+const AGC_SMOOTHING_MS: f32 = 1500.0;
+
+/// Frames quieter than this are treated as pauses and hold the gain.
+const AGC_GATE_DBFS: f32 = -50.0;
+
+/// Peaks above this are softly limited after gain is applied.
+const LIMIT_THRESHOLD: f32 = 0.9;
+
+/// Cleanup applied to recorded speech before it is transcribed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DspConfig {
+    /// Remove DC offset and rumble below `high_pass_hz`.
+    pub high_pass: bool,
+    pub high_pass_hz: f32,
+    /// Spectral subtraction of steady background noise such as fans.
+    pub noise_suppression: bool,
+    /// Multiple of the noise estimate subtracted; higher removes more noise
+    /// at the cost of speech detail.
+    pub noise_suppression_strength: f32,
+    /// Slow automatic gain control towards `target_dbfs`.
+    pub normalize: bool,
+    /// Speech level aimed for, in dB relative to full scale.
+    pub target_dbfs: f32,
+    /// Most the gain control will amplify, so a near-silent recording is
+    /// not raised to noise.
+    pub max_gain_db: f32,
+}
+
+impl Default for DspConfig {
+    fn default() -> Self {
+        Self {
+            high_pass: true,
+            high_pass_hz: 80.0,
+            noise_suppression: false,
+            noise_suppression_strength: 1.5,
+            normalize: true,
+            target_dbfs: -20.0,
+            max_gain_db: 24.0,
+        }
+    }
+}
+
+/// Run the enabled stages over 16kHz mono audio, in order: high-pass,
+/// noise suppression, gain control.
+pub fn prepare_for_upload(mut audio: Vec<f32>, config: &DspConfig) -> Vec<f32> {
+    if audio.is_empty() {
+        return audio;
+    }
+    if config.high_pass {
+        high_pass(&mut audio, DSP_RATE, config.high_pass_hz);
+    }
+    if config.noise_suppression {
+        audio = suppress_noise(&audio, config.noise_suppression_strength);
+    }
+    if config.normalize {
+        normalize(&mut audio, DSP_RATE, config.target_dbfs, config.max_gain_db);
+    }
+    audio
+}
+
+/// Second-order Butterworth high-pass, in place.
+fn high_pass(audio: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    let nyquist = sample_rate as f32 / 2.0;
+    if cutoff_hz <= 0.0 || cutoff_hz >= nyquist {
+        return;
+    }
+    // RBJ cookbook coefficients with Q = 1/sqrt(2)
+    let w0 = 2.0 * PI * cutoff_hz / sample_rate as f32;
+    let alpha = w0.sin() / std::f32::consts::SQRT_2;
+    let cos = w0.cos();
+    let a0 = 1.0 + alpha;
+    let b0 = (1.0 + cos) / 2.0 / a0;
+    let b1 = -(1.0 + cos) / a0;
+    let b2 = b0;
+    let a1 = -2.0 * cos / a0;
+    let a2 = (1.0 - alpha) / a0;
+
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+    for sample in audio.iter_mut() {
+        let x = *sample;
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        (x2, x1, y2, y1) = (x1, x, y1, y);
+        *sample = y;
+    }
+}
+
+/// Spectral subtraction. The noise spectrum is estimated from the quietest
+/// frames of the recording itself, which for dictation are the pauses.
+///
+/// Spectra are computed again for each pass rather than kept, so memory
+/// stays at a few values per frame however long the recording is.
+fn suppress_noise(audio: &[f32], strength: f32) -> Vec<f32> {
+    // sqrt-Hann for analysis and synthesis sums to one at 50% overlap
+    let window: Vec<f32> = (0..FFT_LEN)
+        .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_LEN as f32).cos()).sqrt())
+        .collect();
+
+    // Frame `f` starts a hop before sample `f * HOP_LEN`, so every sample
+    // is covered by two frames; audio outside the recording is silence
+    let frames = audio.len().div_ceil(HOP_LEN) + 1;
+    let frame_start = |f: usize| (f * HOP_LEN) as isize - HOP_LEN as isize;
+    let spectrum = |f: usize| -> (Vec<f32>, Vec<f32>) {
+        let start = frame_start(f);
+        let mut re: Vec<f32> = window
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let at = start + i as isize;
+                let sample = usize::try_from(at)
+                    .ok()
+                    .and_then(|at| audio.get(at))
+                    .copied()
+                    .unwrap_or(0.0);
+                sample * w
+            })
+            .collect();
+        let mut im = vec![0.0f32; FFT_LEN];
+        fft(&mut re, &mut im, false);
+        (re, im)
+    };
+
+    let bins = FFT_LEN / 2 + 1;
+    let power = |(re, im): &(Vec<f32>, Vec<f32>), k: usize| re[k] * re[k] + im[k] * im[k];
+
+    // First pass: find the quietest frames, then average their spectra
+    let mut by_energy: Vec<(usize, f32)> = (0..frames)
+        .map(|f| {
+            let spectrum = spectrum(f);
+            (f, (0..bins).map(|k| power(&spectrum, k)).sum())
+        })
+        .collect();
+    by_energy.sort_by(|a, b| a.1.total_cmp(&b.1));
+    by_energy.truncate(((frames as f32 * NOISE_PERCENTILE) as usize).max(1));
+    let mut noise = vec![0.0f32; bins];
+    for &(f, _) in &by_energy {
+        let spectrum = spectrum(f);
+        for (k, noise) in noise.iter_mut().enumerate() {
+            *noise += power(&spectrum, k);
+        }
+    }
+    noise.iter_mut().for_each(|n| *n /= by_energy.len() as f32);
+
+    // Second pass: filter each frame and add it into the output
+    let floor = SUPPRESSION_FLOOR * SUPPRESSION_FLOOR;
+    let mut output = vec![0.0f32; audio.len()];
+    let mut smoothed = noise.clone();
+    for f in 0..frames {
+        let mut spectrum = spectrum(f);
+        for k in 0..bins {
+            // Averaging over frames steadies the noise bins
+            let p = POWER_SMOOTHING * smoothed[k] + (1.0 - POWER_SMOOTHING) * power(&spectrum, k);
+            smoothed[k] = p;
+            let gain = if p > 0.0 {
+                (1.0 - strength * noise[k] / p).max(floor).sqrt()
+            } else {
+                SUPPRESSION_FLOOR
+            };
+            // Keep the spectrum conjugate-symmetric so the output is real
+            let (re, im) = &mut spectrum;
+            re[k] *= gain;
+            im[k] *= gain;
+            if k != 0 && k != FFT_LEN / 2 {
+                re[FFT_LEN - k] *= gain;
+                im[FFT_LEN - k] *= gain;
+            }
+        }
+        let (re, im) = &mut spectrum;
+        fft(re, im, true);
+        let start = frame_start(f);
+        for (i, (s, w)) in re.iter().zip(&window).enumerate() {
+            if let Some(out) = usize::try_from(start + i as isize)
+                .ok()
+                .and_then(|at| output.get_mut(at))
+            {
+                *out += s * w;
+            }
+        }
+    }
+    output
+}
+
+/// In-place radix-2 FFT; `re.len()` must be a power of two. The inverse is
+/// scaled by `1/n`.
+fn fft(re: &mut [f32], im: &mut [f32], inverse: bool) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f32;
+        re.iter_mut().chain(im.iter_mut()).for_each(|x| *x *= scale);
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    20.0 * level.max(1e-9).log10()
+}
+
+/// Automatic gain control: each frame of speech is pulled towards
+/// `target_dbfs`, smoothed in both directions so the gain moves slowly and
+/// does not lag behind level changes.
+fn normalize(audio: &mut [f32], sample_rate: u32, target_dbfs: f32, max_gain_db: f32) {
+    let frame_len = (sample_rate * AGC_FRAME_MS / 1000) as usize;
+    let levels: Vec<f32> = audio.chunks(frame_len).map(|f| to_db(rms(f))).collect();
+    if levels.iter().all(|&level| level < AGC_GATE_DBFS) {
+        return;
+    }
+
+    // Pauses hold the gain of the speech before them, or after them at
+    // the start
+    let mut wanted = Vec::with_capacity(levels.len());
+    let mut held = None;
+    for &level in &levels {
+        if level >= AGC_GATE_DBFS {
+            held = Some((target_dbfs - level).min(max_gain_db));
+        }
+        wanted.push(held);
+    }
+    let first = wanted.iter().find_map(|&g| g).unwrap_or(0.0);
+    let wanted: Vec<f32> = wanted.into_iter().map(|g| g.unwrap_or(first)).collect();
+
+    // One-pole smoothing forwards and backwards, averaged
+    let coeff = (-(AGC_FRAME_MS as f32) / AGC_SMOOTHING_MS).exp();
+    let mut forward = wanted.clone();
+    for i in 1..forward.len() {
+        forward[i] = coeff * forward[i - 1] + (1.0 - coeff) * wanted[i];
+    }
+    let mut backward = wanted.clone();
+    for i in (0..backward.len() - 1).rev() {
+        backward[i] = coeff * backward[i + 1] + (1.0 - coeff) * wanted[i];
+    }
+    let gains_db: Vec<f32> = forward
+        .iter()
+        .zip(&backward)
+        .map(|(f, b)| (f + b) / 2.0)
+        .collect();
+
+    // Interpolate between frame centres so the gain has no steps
+    let gains: Vec<f32> = gains_db.iter().map(|db| 10f32.powf(db / 20.0)).collect();
+    let last = gains.len() - 1;
+    for (i, sample) in audio.iter_mut().enumerate() {
+        let pos = (i as f32 / frame_len as f32 - 0.5).max(0.0);
+        let frame = (pos as usize).min(last);
+        let next = (frame + 1).min(last);
+        let t = pos - frame as f32;
+        let gain = gains[frame] + (gains[next] - gains[frame]) * t.min(1.0);
+        *sample = soft_limit(*sample * gain);
+    }
+}
+
+/// Pass samples below [`LIMIT_THRESHOLD`] unchanged and bend larger ones
+/// smoothly towards full scale.
+fn soft_limit(x: f32) -> f32 {
+    let magnitude = x.abs();
+    if magnitude <= LIMIT_THRESHOLD {
+        return x;
+    }
+    let headroom = 1.0 - LIMIT_THRESHOLD;
+    let over = (magnitude - LIMIT_THRESHOLD) / headroom;
+    (LIMIT_THRESHOLD + headroom * over.tanh()).copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, secs: f32) -> Vec<f32> {
+        (0..(DSP_RATE as f32 * secs) as usize)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f32 / DSP_RATE as f32).sin())
+            .collect()
+    }
+
+    /// Deterministic white noise in -amplitude..amplitude.
+    fn noise(amplitude: f32, len: usize) -> Vec<f32> {
+        let mut state = 0x2545_f491u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                amplitude * (state as f32 / u32::MAX as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_high_pass_removes_dc_and_keeps_speech_band() {
+        let mut dc = vec![0.5f32; DSP_RATE as usize];
+        high_pass(&mut dc, DSP_RATE, 80.0);
+        assert!(rms(&dc[8000..]) < 0.001);
+
+        let mut voice = sine(1000.0, 0.5, 1.0);
+        let before = rms(&voice);
+        high_pass(&mut voice, DSP_RATE, 80.0);
+        assert!((rms(&voice[1600..]) - before).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_fft_round_trip() {
+        let original = noise(1.0, FFT_LEN);
+        let mut re = original.clone();
+        let mut im = vec![0.0; FFT_LEN];
+        fft(&mut re, &mut im, false);
+        fft(&mut re, &mut im, true);
+        for (a, b) in re.iter().zip(&original) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_noise_suppression_keeps_tone_and_reduces_noise() {
+        let len = DSP_RATE as usize * 2;
+        let background = noise(0.05, len);
+        // Noise alone for the first half, noise plus a tone after
+        let tone = sine(440.0, 0.3, 1.0);
+        let mut audio = background.clone();
+        for (s, t) in audio[len / 2..].iter_mut().zip(&tone) {
+            *s += t;
+        }
+
+        let cleaned = suppress_noise(&audio, 1.5);
+        assert_eq!(cleaned.len(), audio.len());
+        assert!(rms(&cleaned[..len / 2]) < rms(&background[..len / 2]) / 3.0);
+        let speech = rms(&cleaned[len / 2 + 1600..len - 1600]);
+        assert!((speech - rms(&tone)).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_normalize_raises_quiet_speech_within_limits() {
+        // -40 dBFS speech is raised to about -20 dBFS
+        let mut quiet = sine(300.0, 0.01 * std::f32::consts::SQRT_2, 3.0);
+        normalize(&mut quiet, DSP_RATE, -20.0, 24.0);
+        assert!((to_db(rms(&quiet[16000..32000])) + 20.0).abs() < 1.0);
+
+        // The gain is capped
+        let mut capped = sine(300.0, 0.01, 3.0);
+        normalize(&mut capped, DSP_RATE, -20.0, 6.0);
+        assert!((to_db(rms(&capped)) - to_db(rms(&sine(300.0, 0.01, 3.0))) - 6.0).abs() < 0.5);
+
+        // Silence is left alone
+        let mut silence = vec![0.0f32; 16000];
+        normalize(&mut silence, DSP_RATE, -20.0, 24.0);
+        assert!(silence.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_soft_limit_stays_below_full_scale() {
+        assert_eq!(soft_limit(0.5), 0.5);
+        assert!(soft_limit(4.0) <= 1.0);
+        assert!(soft_limit(-4.0) >= -1.0);
+        assert!(soft_limit(0.95) > 0.9);
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Container and codec audio is uploaded in. FLAC is lossless at roughly
+/// half the size of WAV; Ogg/Opus is lossy but around a tenth of FLAC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioEncoding {
+    #[default]
+    Wav,
+    Flac,
+    OggOpus,
+}
+
+impl AudioEncoding {
+    /// Encodings this build can produce; Ogg/Opus needs the `ogg-opus`
+    /// feature.
+    pub fn available() -> Vec<AudioEncoding> {
+        [Self::Wav, Self::Flac, Self::OggOpus]
+            .into_iter()
+            .filter(|encoding| encoding.is_available())
+            .collect()
+    }
+
+    pub fn is_available(&self) -> bool {
+        *self != AudioEncoding::OggOpus || cfg!(feature = "ogg-opus")
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "wav",
+            AudioEncoding::Flac => "flac",
+            AudioEncoding::OggOpus => "ogg",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav => "audio/wav",
+            AudioEncoding::Flac => "audio/flac",
+            AudioEncoding::OggOpus => "audio/ogg",
+        }
+    }
+
+    /// Encode mono f32 samples.
+    pub fn encode(&self, samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+        match self {
+            AudioEncoding::Wav => Ok(super::encode_wav(samples, sample_rate)),
+            AudioEncoding::Flac => encode_flac(samples, sample_rate),
+            AudioEncoding::OggOpus => encode_ogg_opus(samples, sample_rate),
+        }
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Encode as 16-bit mono FLAC.
+pub fn encode_flac(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    let pcm: Vec<i32> = samples.iter().map(|&s| to_i16(s) as i32).collect();
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| anyhow!("Invalid FLAC encoder config: {:?}", e))?;
+    let source = flacenc::source::MemSource::from_samples(&pcm, 1, 16, sample_rate as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| anyhow!("FLAC encoding failed: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| anyhow!("FLAC encoding failed: {:?}", e))?;
+    Ok(sink.as_slice().to_vec())
+}
+
+/// Encode as mono Opus in an Ogg container. Opus only runs at 8, 12, 16,
+/// 24 or 48kHz, which covers the 16kHz audio sent to providers.
+#[cfg(feature = "ogg-opus")]
+pub fn encode_ogg_opus(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+    use ogg::writing::PacketWriteEndInfo;
+
+    /// 20ms frames, the usual size for speech.
+    const FRAME_MS: usize = 20;
+    /// Granule positions always count 48kHz samples.
+    const GRANULE_RATE: u64 = 48000;
+    const SERIAL: u32 = 1;
+
+    let mut encoder =
+        opus::Encoder::new(sample_rate, opus::Channels::Mono, opus::Application::Voip)?;
+    encoder.set_bitrate(opus::Bitrate::Bits(32000))?;
+    let scale = GRANULE_RATE / sample_rate as u64;
+    let pre_skip = encoder.get_lookahead()? as u64 * scale;
+
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+    head.extend_from_slice(&sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+
+    let vendor = b"whisper";
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // user comments
+
+    let mut writer = ogg::PacketWriter::new(Vec::new());
+    writer.write_packet(head, SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+    writer.write_packet(tags, SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    let frame_len = sample_rate as usize * FRAME_MS / 1000;
+    let frames = samples.len().div_ceil(frame_len).max(1);
+    let mut frame = vec![0.0f32; frame_len];
+    let mut packet = vec![0u8; 4000];
+    for i in 0..frames {
+        let chunk = samples.get(i * frame_len..).unwrap_or_default();
+        let chunk = &chunk[..chunk.len().min(frame_len)];
+        frame[..chunk.len()].copy_from_slice(chunk);
+        frame[chunk.len()..].fill(0.0);
+
+        let len = encoder.encode_float(&frame, &mut packet)?;
+        let (end, decoded) = if i + 1 == frames {
+            (PacketWriteEndInfo::EndStream, samples.len() as u64)
+        } else {
+            (
+                PacketWriteEndInfo::NormalPacket,
+                ((i + 1) * frame_len) as u64,
+            )
+        };
+        writer.write_packet(
+            packet[..len].to_vec(),
+            SERIAL,
+            end,
+            pre_skip + decoded * scale,
+        )?;
+    }
+    Ok(writer.into_inner())
+}
+
+#[cfg(not(feature = "ogg-opus"))]
+pub fn encode_ogg_opus(_samples: &[f32], _sample_rate: u32) -> Result<Vec<u8>> {
+    Err(anyhow!(
+        "Ogg/Opus encoding not enabled. Rebuild with --features ogg-opus (requires CMake)."
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone() -> Vec<f32> {
+        (0..16000).map(|i| 0.5 * (i as f32 * 0.1).sin()).collect()
+    }
+
+    #[test]
+    fn test_flac_is_smaller_than_wav() {
+        let audio = tone();
+        let flac = AudioEncoding::Flac.encode(&audio, 16000).unwrap();
+        let wav = AudioEncoding::Wav.encode(&audio, 16000).unwrap();
+        assert_eq!(&flac[..4], b"fLaC");
+        assert!(flac.len() < wav.len());
+    }
+
+    #[test]
+    fn test_available_encodings() {
+        let available = AudioEncoding::available();
+        assert!(available.contains(&AudioEncoding::Flac));
+        assert_eq!(
+            available.contains(&AudioEncoding::OggOpus),
+            AudioEncoding::OggOpus.encode(&tone(), 16000).is_ok()
+        );
+    }
+
+    #[cfg(feature = "ogg-opus")]
+    #[test]
+    fn test_ogg_opus_has_opus_headers() {
+        let ogg = AudioEncoding::OggOpus.encode(&tone(), 16000).unwrap();
+        assert_eq!(&ogg[..4], b"OggS");
+        assert!(ogg.windows(8).any(|w| w == b"OpusHead"));
+        assert!(ogg.windows(8).any(|w| w == b"OpusTags"));
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::capture::CaptureConfig;
+use super::{AudioRecorder, Recording, RecordingOptions};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum CaptureState {
+    Idle,
+    Recording,
+    Paused,
+    /// The input device was lost and capture could not continue; stopping
+    /// returns the audio captured before the loss.
+    Interrupted,
+}
+
+/// How often the engine checks for errors reported by the stream.
+const FAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Emitted to the frontend as `stream-error`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamErrorEvent {
+    pub message: String,
+    pub device_lost: bool,
+    /// Device capture continues on after a failover or reopen.
+    pub recovered_on: Option<String>,
+    /// A recording was cut short; its audio so far is still available.
+    pub interrupted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineStatus {
+    pub state: CaptureState,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// The input stream is kept open between recordings for pre-roll.
+    pub armed: bool,
+}
+
+enum EngineCommand {
+    Start {
+        options: RecordingOptions,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Stop {
+        reply: oneshot::Sender<Result<Recording>>,
+    },
+    Pause {
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Resume {
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Arm {
+        device_name: Option<String>,
+        capture: CaptureConfig,
+        preroll_ms: u32,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Disarm {
+        reply: oneshot::Sender<()>,
+    },
+    Query {
+        reply: oneshot::Sender<EngineStatus>,
+    },
+}
+
+/// Handle to the audio engine thread, which owns the [`AudioRecorder`] and
+/// its `cpal::Stream` for the lifetime of the app. Requests are sent over a
+/// channel and answered on a oneshot, so callers never share the stream or
+/// hold a lock while audio is being set up or torn down.
+#[derive(Clone)]
+pub struct AudioEngine {
+    tx: mpsc::Sender<EngineCommand>,
+}
+
+impl AudioEngine {
+    /// Spawn the engine thread. `on_stream_error` is called from that
+    /// thread whenever the input stream reports an error.
+    pub fn spawn(on_stream_error: impl Fn(StreamErrorEvent) + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("audio-engine".into())
+            .spawn(move || run(rx, on_stream_error))
+            .expect("failed to spawn audio engine thread");
+        Self { tx }
+    }
+
+    pub async fn start(&self, options: RecordingOptions) -> Result<()> {
+        self.request(|reply| EngineCommand::Start { options, reply })
+            .await?
+    }
+
+    pub async fn stop(&self) -> Result<Recording> {
+        self.request(|reply| EngineCommand::Stop { reply }).await?
+    }
+
+    pub async fn pause(&self) -> Result<()> {
+        self.request(|reply| EngineCommand::Pause { reply }).await?
+    }
+
+    pub async fn resume(&self) -> Result<()> {
+        self.request(|reply| EngineCommand::Resume { reply })
+            .await?
+    }
+
+    pub async fn arm(
+        &self,
+        device_name: Option<String>,
+        capture: CaptureConfig,
+        preroll_ms: u32,
+    ) -> Result<()> {
+        self.request(|reply| EngineCommand::Arm {
+            device_name,
+            capture,
+            preroll_ms,
+            reply,
+        })
+        .await?
+    }
+
+    pub async fn disarm(&self) -> Result<()> {
+        self.request(|reply| EngineCommand::Disarm { reply }).await
+    }
+
+    pub async fn status(&self) -> Result<EngineStatus> {
+        self.request(|reply| EngineCommand::Query { reply }).await
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> EngineCommand,
+    ) -> Result<T> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(command(reply))
+            .map_err(|_| anyhow!("Audio engine is not running"))?;
+        rx.await
+            .map_err(|_| anyhow!("Audio engine dropped the request"))
+    }
+}
+
+/// Engine thread main loop; exits once every handle has been dropped.
+fn run(rx: mpsc::Receiver<EngineCommand>, on_stream_error: impl Fn(StreamErrorEvent)) {
+    let mut recorder = AudioRecorder::new();
+
+    loop {
+        let command = match rx.recv_timeout(FAULT_POLL_INTERVAL) {
+            Ok(command) => command,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if let Some(event) = handle_fault(&mut recorder) {
+                    on_stream_error(event);
+                }
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+
+        match command {
+            EngineCommand::Start { options, reply } => {
+                let _ = reply.send(recorder.start(options));
+            }
+            EngineCommand::Stop { reply } => {
+                let _ = reply.send(recorder.stop());
+            }
+            EngineCommand::Pause { reply } => {
+                let _ = reply.send(recorder.pause());
+            }
+            EngineCommand::Resume { reply } => {
+                let _ = reply.send(recorder.resume());
+            }
+            EngineCommand::Arm {
+                device_name,
+                capture,
+                preroll_ms,
+                reply,
+            } => {
+                let _ = reply.send(recorder.arm(&device_name, &capture, preroll_ms));
+            }
+            EngineCommand::Disarm { reply } => {
+                recorder.disarm();
+                let _ = reply.send(());
+            }
+            EngineCommand::Query { reply } => {
+                let _ = reply.send(EngineStatus {
+                    state: recorder.state(),
+                    sample_rate: recorder.sample_rate(),
+                    channels: recorder.channels(),
+                    armed: recorder.is_armed(),
+                });
+            }
+        }
+    }
+
+    log::info!("Audio engine stopped");
+}
+
+/// Let the recorder recover from a reported stream error, if there is one.
+fn handle_fault(recorder: &mut AudioRecorder) -> Option<StreamErrorEvent> {
+    let fault = recorder.take_fault()?;
+    let (recovered_on, interrupted) = match recorder.recover(&fault) {
+        Ok(device) => (device, false),
+        Err(e) => {
+            log::error!("Audio stream could not recover: {:#}", e);
+            (None, recorder.state() == CaptureState::Interrupted)
+        }
+    };
+    Some(StreamErrorEvent {
+        message: fault.message,
+        device_lost: fault.device_lost,
+        recovered_on,
+        interrupted,
+    })
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+/// How often the stream callback publishes a new level reading.
+const LEVEL_WINDOW_MS: u32 = 50;
+
+/// Samples at or above this magnitude count as clipped.
+const CLIP_THRESHOLD: f32 = 0.999;
+
+/// Input level over one metering window, emitted as `audio-level`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AudioLevel {
+    pub rms: f32,
+    pub peak: f32,
+    pub clipping: bool,
+}
+
+/// Accumulates levels inside the stream callback and produces a reading
+/// once per window. Never allocates or blocks.
+pub struct LevelMeter {
+    window: usize,
+    count: usize,
+    sum_sq: f32,
+    peak: f32,
+    clipping: bool,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let window =
+            sample_rate as usize * channels.max(1) as usize * LEVEL_WINDOW_MS as usize / 1000;
+        Self {
+            window: window.max(1),
+            count: 0,
+            sum_sq: 0.0,
+            peak: 0.0,
+            clipping: false,
+        }
+    }
+
+    /// Feed samples, returning the reading for the last completed window.
+    pub fn process(&mut self, samples: impl Iterator<Item = f32>) -> Option<AudioLevel> {
+        let mut level = None;
+        for sample in samples {
+            let magnitude = sample.abs();
+            self.sum_sq += sample * sample;
+            self.peak = self.peak.max(magnitude);
+            self.clipping |= magnitude >= CLIP_THRESHOLD;
+            self.count += 1;
+
+            if self.count == self.window {
+                level = Some(AudioLevel {
+                    rms: (self.sum_sq / self.count as f32).sqrt(),
+                    peak: self.peak,
+                    clipping: self.clipping,
+                });
+                self.count = 0;
+                self.sum_sq = 0.0;
+                self.peak = 0.0;
+                self.clipping = false;
+            }
+        }
+        level
+    }
+}
+
+/// Latest reading, handed from the stream callback to the consumer thread
+/// without locking. `sequence` changes on every publish.
+#[derive(Default)]
+pub struct SharedLevel {
+    rms: AtomicU32,
+    peak: AtomicU32,
+    clipping: AtomicBool,
+    sequence: AtomicU64,
+}
+
+impl SharedLevel {
+    pub fn publish(&self, level: AudioLevel) {
+        self.rms.store(level.rms.to_bits(), Ordering::Relaxed);
+        self.peak.store(level.peak.to_bits(), Ordering::Relaxed);
+        self.clipping.store(level.clipping, Ordering::Relaxed);
+        self.sequence.fetch_add(1, Ordering::Release);
+    }
+
+    /// Return the latest reading if it was published after `last_seen`.
+    pub fn read_if_newer(&self, last_seen: &mut u64) -> Option<AudioLevel> {
+        let sequence = self.sequence.load(Ordering::Acquire);
+        if sequence == *last_seen {
+            return None;
+        }
+        *last_seen = sequence;
+        Some(AudioLevel {
+            rms: f32::from_bits(self.rms.load(Ordering::Relaxed)),
+            peak: f32::from_bits(self.peak.load(Ordering::Relaxed)),
+            clipping: self.clipping.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_once_per_window() {
+        // 50ms at 1kHz mono = 50 samples per window
+        let mut meter = LevelMeter::new(1000, 1);
+        assert_eq!(meter.process(std::iter::repeat_n(0.5, 49)), None);
+
+        let level = meter.process(std::iter::repeat_n(0.5, 1)).unwrap();
+        assert!((level.rms - 0.5).abs() < 1e-6);
+        assert!((level.peak - 0.5).abs() < 1e-6);
+        assert!(!level.clipping);
+    }
+
+    #[test]
+    fn test_detects_clipping_and_resets() {
+        let mut meter = LevelMeter::new(1000, 1);
+        let mut block = vec![0.1f32; 50];
+        block[10] = -1.0;
+        let level = meter.process(block.into_iter()).unwrap();
+        assert!(level.clipping);
+        assert_eq!(level.peak, 1.0);
+
+        let level = meter.process(std::iter::repeat_n(0.1, 50)).unwrap();
+        assert!(!level.clipping);
+    }
+
+    #[test]
+    fn test_shared_level_only_returns_new_readings() {
+        let shared = SharedLevel::default();
+        let mut last_seen = 0;
+        assert_eq!(shared.read_if_newer(&mut last_seen), None);
+
+        let level = AudioLevel {
+            rms: 0.2,
+            peak: 0.7,
+            clipping: false,
+        };
+        shared.publish(level);
+        assert_eq!(shared.read_if_newer(&mut last_seen), Some(level));
+        assert_eq!(shared.read_if_newer(&mut last_seen), None);
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+/// Rate of the audio the recorded time is measured on.
+const LIMIT_RATE: u64 = 16000;
+
+/// Upper bound on recording length, so a forgotten recording ends itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaxDurationConfig {
+    pub enabled: bool,
+    /// Recorded time after which the recording stops. Paused time does not
+    /// count.
+    pub max_secs: u32,
+    /// How long before the limit the warning is sent.
+    pub warning_secs: u32,
+}
+
+impl Default for MaxDurationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_secs: 7200,
+            warning_secs: 60,
+        }
+    }
+}
+
+/// Emitted to the frontend as `max-duration-warning`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MaxDurationWarning {
+    pub remaining_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Callback-driven length limit: the recorder counts recorded time, calls
+/// `on_warning` once when the warning point is reached and `on_limit` once
+/// at the limit.
+pub struct DurationLimit {
+    pub config: MaxDurationConfig,
+    pub on_warning: Box<dyn Fn(MaxDurationWarning) + Send + 'static>,
+    pub on_limit: Box<dyn Fn() + Send + 'static>,
+}
+
+/// Tracks a recording's 16kHz output against its [`DurationLimit`].
+pub struct DurationWatch {
+    limit: DurationLimit,
+    samples: u64,
+    warned: bool,
+    reached: bool,
+}
+
+impl DurationWatch {
+    pub fn new(limit: DurationLimit) -> Self {
+        Self {
+            limit,
+            samples: 0,
+            warned: false,
+            reached: false,
+        }
+    }
+
+    /// Count `samples` more of 16kHz audio.
+    pub fn advance(&mut self, samples: usize) {
+        if self.reached {
+            return;
+        }
+        self.samples += samples as u64;
+        let elapsed_ms = self.samples * 1000 / LIMIT_RATE;
+        let max_ms = self.limit.config.max_secs as u64 * 1000;
+        let warning_ms = self.limit.config.warning_secs as u64 * 1000;
+
+        if !self.warned && warning_ms > 0 && elapsed_ms + warning_ms >= max_ms {
+            self.warned = true;
+            (self.limit.on_warning)(MaxDurationWarning {
+                remaining_ms: max_ms.saturating_sub(elapsed_ms),
+                max_ms,
+            });
+        }
+        if elapsed_ms >= max_ms {
+            self.reached = true;
+            (self.limit.on_limit)();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    fn watch(max_secs: u32, warning_secs: u32) -> (DurationWatch, Arc<AtomicU64>, Arc<AtomicU64>) {
+        let warnings = Arc::new(AtomicU64::new(0));
+        let limits = Arc::new(AtomicU64::new(0));
+        let (w, l) = (warnings.clone(), limits.clone());
+        let watch = DurationWatch::new(DurationLimit {
+            config: MaxDurationConfig {
+                enabled: true,
+                max_secs,
+                warning_secs,
+            },
+            on_warning: Box::new(move |warning| {
+                w.fetch_add(1, Ordering::Relaxed);
+                assert!(warning.remaining_ms <= warning_secs as u64 * 1000);
+            }),
+            on_limit: Box::new(move || {
+                l.fetch_add(1, Ordering::Relaxed);
+            }),
+        });
+        (watch, warnings, limits)
+    }
+
+    #[test]
+    fn test_warns_then_stops_once() {
+        let (mut watch, warnings, limits) = watch(10, 3);
+        // 6s, then 1s at a time up to 12s
+        watch.advance(6 * 16000);
+        assert_eq!(warnings.load(Ordering::Relaxed), 0);
+        watch.advance(16000);
+        assert_eq!(warnings.load(Ordering::Relaxed), 1);
+        assert_eq!(limits.load(Ordering::Relaxed), 0);
+        for _ in 0..5 {
+            watch.advance(16000);
+        }
+        assert_eq!(warnings.load(Ordering::Relaxed), 1);
+        assert_eq!(limits.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_zero_warning_only_stops() {
+        let (mut watch, warnings, limits) = watch(1, 0);
+        watch.advance(16000);
+        assert_eq!(warnings.load(Ordering::Relaxed), 0);
+        assert_eq!(limits.load(Ordering::Relaxed), 1);
+    }
+}
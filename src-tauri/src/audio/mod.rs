@@ -1,12 +1,38 @@
+pub mod capture;
+pub mod decode;
+pub mod diagnostics;
+pub mod dsp;
+pub mod encode;
+pub mod engine;
+pub mod level;
+pub mod limit;
+pub mod preroll;
 pub mod resampler;
+pub mod spill;
+pub mod trim;
+pub mod vad;
 
 use anyhow::{anyhow, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::Stream;
+use cpal::{FromSample, SizedSample, Stream};
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use capture::{CaptureConfig, ResolvedCapture};
+use engine::CaptureState;
+use level::{AudioLevel, LevelMeter, SharedLevel};
+use limit::{DurationLimit, DurationWatch};
+use preroll::PreRollBuffer;
+use resampler::{ResampleQuality, StreamingResampler};
+use spill::{RecordedAudio, RecordingBuffer, SpillTarget};
+use vad::{AutoStop, VoiceActivityDetector};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AudioDevice {
     pub name: String,
     pub is_default: bool,
@@ -31,35 +57,158 @@ pub fn list_input_devices() -> Vec<AudioDevice> {
     devices
 }
 
-/// Find an input device by name, falling back to the default device.
-fn find_device(device_name: &Option<String>) -> Result<cpal::Device> {
-    let host = cpal::default_host();
+/// How often the device list is polled for hot-plug changes.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
-    if let Some(name) = device_name {
-        if let Ok(input_devices) = host.input_devices() {
-            for device in input_devices {
-                if device.name().ok().as_deref() == Some(name.as_str()) {
-                    return Ok(device);
+/// Poll the input device list on a background thread and call `on_change`
+/// whenever a device is added or removed or the default changes.
+pub fn watch_input_devices(on_change: impl Fn(Vec<AudioDevice>) + Send + 'static) -> Result<()> {
+    std::thread::Builder::new()
+        .name("device-watcher".into())
+        .spawn(move || {
+            let mut known = list_input_devices();
+            loop {
+                std::thread::sleep(DEVICE_POLL_INTERVAL);
+                let devices = list_input_devices();
+                if devices != known {
+                    log::info!("Input devices changed: {} available", devices.len());
+                    on_change(devices.clone());
+                    known = devices;
                 }
             }
+        })?;
+    Ok(())
+}
+
+/// Find a connected input device by exact name.
+fn find_named_device(name: &str) -> Option<cpal::Device> {
+    cpal::default_host()
+        .input_devices()
+        .ok()?
+        .find(|device| device.name().ok().as_deref() == Some(name))
+}
+
+/// Find an input device by name, falling back to the default device.
+fn find_device(device_name: &Option<String>) -> Result<cpal::Device> {
+    if let Some(name) = device_name {
+        if let Some(device) = find_named_device(name) {
+            return Ok(device);
         }
         log::warn!("Input device '{}' not found, falling back to default", name);
     }
 
-    host.default_input_device()
+    cpal::default_host()
+        .default_input_device()
         .ok_or_else(|| anyhow!("No input device found"))
 }
 
+/// Per-recording options passed to [`AudioRecorder::start`].
+#[derive(Default)]
+pub struct RecordingOptions {
+    pub device_name: Option<String>,
+    pub capture: CaptureConfig,
+    pub resample_quality: ResampleQuality,
+    /// When set, the samples are also fed to a voice activity detector that
+    /// invokes its callback once trailing silence exceeds the timeout.
+    pub auto_stop: Option<AutoStop>,
+    /// When set, the recorded time is checked against a maximum length.
+    pub max_duration: Option<DurationLimit>,
+    /// Receives a throttled input level reading while recording.
+    pub on_level: Option<Box<dyn Fn(AudioLevel) + Send + 'static>>,
+    /// Receives the 16kHz mono audio as it is recorded, for transcribing
+    /// while the user is still speaking.
+    pub on_audio: Option<OnAudio>,
+    /// Continue on the default device if the input device disappears
+    /// mid-recording, keeping the audio captured so far.
+    pub failover: bool,
+    /// Move the recording to disk once it grows long; kept in memory when
+    /// unset.
+    pub spill: Option<SpillTarget>,
+}
+
+/// Seconds of device-rate audio the ring buffer between the stream callback
+/// and the consumer thread can hold before samples are dropped.
+const RING_BUFFER_SECONDS: usize = 2;
+
+/// Callback invoked when the voice activity detector fires.
+type OnSilence = Box<dyn Fn() + Send + 'static>;
+
+/// Callback receiving input level readings.
+type OnLevel = Box<dyn Fn(AudioLevel) + Send + 'static>;
+
+/// Callback receiving recorded 16kHz audio.
+pub type OnAudio = Box<dyn FnMut(&[f32]) + Send + 'static>;
+
+/// Samples the stream callback could not hand to the consumer thread.
+#[derive(Default)]
+struct OverrunCounter {
+    events: AtomicU64,
+    samples: AtomicU64,
+}
+
+impl OverrunCounter {
+    fn reset(&self) {
+        self.events.store(0, Ordering::Relaxed);
+        self.samples.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Capture statistics for a finished recording.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordingStats {
+    pub duration_ms: u64,
+    /// Callbacks whose samples did not fully fit in the ring buffer.
+    pub overruns: u64,
+    /// Device-rate samples lost to those overruns.
+    pub dropped_samples: u64,
+}
+
+/// A finished recording: 16kHz mono audio plus capture statistics.
+pub struct Recording {
+    pub audio: RecordedAudio,
+    pub stats: RecordingStats,
+}
+
+/// Parameters the armed stream was opened with, kept so it can be reopened
+/// after the device goes away.
+#[derive(Clone)]
+struct ArmConfig {
+    device_name: Option<String>,
+    capture: CaptureConfig,
+    preroll_ms: u32,
+}
+
+/// Error reported by the stream's error callback.
+#[derive(Debug, Clone)]
+pub struct StreamFault {
+    pub message: String,
+    /// The device disappeared and the stream will not deliver more data.
+    pub device_lost: bool,
+}
+
+/// Owns the input stream while recording, or for as long as it is armed.
+/// `cpal::Stream` is not `Send` on every platform, so the recorder lives on
+/// the audio engine thread; the rest of the app talks to it through
+/// [`engine::AudioEngine`].
 pub struct AudioRecorder {
     stream: Option<Stream>,
-    buffer: Arc<Mutex<Vec<f32>>>,
+    consumer: Option<ConsumerHandle>,
+    /// Keep the stream open between recordings, buffering pre-roll.
+    armed: Option<ArmConfig>,
+    recording: bool,
+    /// Fail over to the default device if the current one is lost.
+    failover: bool,
+    /// Audio captured before the device was lost, when the stream could not
+    /// be reopened. Returned by `stop`.
+    interrupted: Option<Box<ActiveRecording>>,
+    paused: Arc<AtomicBool>,
+    overruns: Arc<OverrunCounter>,
+    level: Arc<SharedLevel>,
+    fault: Arc<Mutex<Option<StreamFault>>>,
     sample_rate: u32,
     channels: u16,
 }
 
-// cpal::Stream is not Send by default on all platforms but we manage it safely
-unsafe impl Send for AudioRecorder {}
-
 impl AudioRecorder {
     pub fn new() -> Self {
         let host = cpal::default_host();
@@ -73,94 +222,609 @@ impl AudioRecorder {
 
         Self {
             stream: None,
-            buffer: Arc::new(Mutex::new(Vec::new())),
+            consumer: None,
+            armed: None,
+            recording: false,
+            failover: false,
+            interrupted: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            overruns: Arc::new(OverrunCounter::default()),
+            level: Arc::new(SharedLevel::default()),
+            fault: Arc::new(Mutex::new(None)),
             sample_rate,
             channels,
         }
     }
 
-    pub fn start(&mut self, device_name: &Option<String>) -> Result<()> {
-        let device = find_device(device_name)?;
+    /// Start capturing. The stream callback only copies samples into a
+    /// lock-free ring buffer; a consumer thread drains it, runs VAD and
+    /// resamples to 16kHz mono, so the audio is ready as soon as `stop`
+    /// returns. When armed, the already-open stream is reused and the
+    /// buffered pre-roll becomes the start of the recording.
+    pub fn start(&mut self, options: RecordingOptions) -> Result<()> {
+        if self.recording {
+            return Err(anyhow!("Already recording"));
+        }
+        if self.consumer.is_none() {
+            self.open(&options.device_name, &options.capture, 0)?;
+        }
 
-        let config = device.default_input_config()?;
-        self.sample_rate = config.sample_rate().0;
-        self.channels = config.channels();
-
-        // Clear existing buffer
-        {
-            let mut buf = self.buffer.lock().unwrap();
-            buf.clear();
-        }
-
-        let buffer_clone = self.buffer.clone();
-        let _channels = self.channels;
-
-        let stream = match config.sample_format() {
-            cpal::SampleFormat::F32 => device.build_input_stream(
-                &config.into(),
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    let mut buf = buffer_clone.lock().unwrap();
-                    buf.extend_from_slice(data);
-                },
-                |err| log::error!("Audio stream error: {}", err),
-                None,
-            )?,
-            cpal::SampleFormat::I16 => {
-                let buffer_clone = self.buffer.clone();
-                device.build_input_stream(
-                    &config.into(),
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        let mut buf = buffer_clone.lock().unwrap();
-                        for &sample in data {
-                            buf.push(sample as f32 / i16::MAX as f32);
-                        }
-                    },
-                    |err| log::error!("Audio stream error: {}", err),
-                    None,
-                )?
-            }
-            format => return Err(anyhow!("Unsupported sample format: {:?}", format)),
-        };
+        let vad = options.auto_stop.map(|a| {
+            (
+                VoiceActivityDetector::new(a.config, self.sample_rate, self.channels),
+                a.on_silence,
+            )
+        });
+        let resampler =
+            StreamingResampler::new(self.sample_rate, self.channels, options.resample_quality);
 
-        stream.play()?;
-        self.stream = Some(stream);
+        self.overruns.reset();
+        self.paused.store(false, Ordering::Relaxed);
+        self.failover = options.failover;
+        if let Some(consumer) = &self.consumer {
+            consumer.send(ConsumerCommand::Begin(Box::new(ActiveRecording {
+                quality: options.resample_quality,
+                resampler,
+                vad,
+                max_duration: options.max_duration.map(DurationWatch::new),
+                on_level: options.on_level,
+                on_audio: options.on_audio,
+                audio: RecordingBuffer::new(options.spill),
+                audio_16k: Vec::new(),
+            })))?;
+        }
+        self.recording = true;
 
         log::info!(
-            "Recording started: {}Hz, {} channels",
+            "Recording started: {}Hz, {} channels{}",
             self.sample_rate,
-            self.channels
+            self.channels,
+            if self.armed.is_some() {
+                " (with pre-roll)"
+            } else {
+                ""
+            }
         );
         Ok(())
     }
 
-    pub fn stop(&mut self) -> Result<Vec<f32>> {
-        // Drop the stream to stop recording
-        self.stream.take();
+    /// Stop capturing and return the recording as 16kHz mono.
+    pub fn stop(&mut self) -> Result<Recording> {
+        if self.armed.is_none() {
+            // Drop the stream first so nothing is pushed after the consumer drains
+            self.stream.take();
+        }
+
+        let audio = if let Some(recording) = self.interrupted.take() {
+            recording.finish()
+        } else {
+            match (&self.consumer, self.recording) {
+                (Some(consumer), true) => consumer.end(),
+                _ => Ok(RecordedAudio::default()),
+            }
+        };
+        self.recording = false;
+        self.paused.store(false, Ordering::Relaxed);
+        if self.armed.is_none() {
+            self.close();
+        } else if self.consumer.is_none() {
+            // The armed stream was lost mid-recording; bring it back
+            self.rearm();
+        }
+        let audio = audio?;
 
-        let raw_audio = {
-            let mut buf = self.buffer.lock().unwrap();
-            let data = buf.clone();
-            buf.clear();
-            data
+        let stats = RecordingStats {
+            duration_ms: audio.len() as u64 * 1000 / 16000,
+            overruns: self.overruns.events.load(Ordering::Relaxed),
+            dropped_samples: self.overruns.samples.load(Ordering::Relaxed),
         };
+        if stats.overruns > 0 {
+            log::warn!(
+                "Audio ring buffer overran {} times, {} samples dropped",
+                stats.overruns,
+                stats.dropped_samples
+            );
+        }
+
+        log::info!(
+            "Recording stopped: {} samples captured at 16kHz{}",
+            audio.len(),
+            if matches!(audio, RecordedAudio::File { .. }) {
+                " (spilled to disk)"
+            } else {
+                ""
+            }
+        );
+        Ok(Recording { audio, stats })
+    }
+
+    /// Open the input stream and keep it running between recordings, holding
+    /// the last `preroll_ms` of audio so the start of a recording is never
+    /// clipped. Re-arming replaces the current stream.
+    pub fn arm(
+        &mut self,
+        device_name: &Option<String>,
+        capture: &CaptureConfig,
+        preroll_ms: u32,
+    ) -> Result<()> {
+        if self.recording {
+            return Err(anyhow!("Cannot arm while recording"));
+        }
+        self.close();
+        self.armed = None;
+        self.open(device_name, capture, preroll_ms)?;
+        self.armed = Some(ArmConfig {
+            device_name: device_name.clone(),
+            capture: capture.clone(),
+            preroll_ms,
+        });
+        log::info!("Recorder armed with {}ms pre-roll", preroll_ms);
+        Ok(())
+    }
+
+    /// Close the armed stream. A recording in progress keeps running and the
+    /// stream is closed when it stops.
+    pub fn disarm(&mut self) {
+        if self.armed.take().is_none() {
+            return;
+        }
+        if !self.recording {
+            self.close();
+        }
+        log::info!("Recorder disarmed");
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed.is_some()
+    }
+
+    /// Drop incoming samples until [`resume`](Self::resume) is called.
+    pub fn pause(&mut self) -> Result<()> {
+        if !self.recording {
+            return Err(anyhow!("Not recording"));
+        }
+        self.paused.store(true, Ordering::Relaxed);
+        Ok(())
+    }
 
-        log::info!("Recording stopped: {} samples captured", raw_audio.len());
-        Ok(raw_audio)
+    pub fn resume(&mut self) -> Result<()> {
+        if !self.recording {
+            return Err(anyhow!("Not recording"));
+        }
+        self.paused.store(false, Ordering::Relaxed);
+        Ok(())
     }
 
-    pub fn get_audio_16khz_mono(&self, raw: Vec<f32>) -> Vec<f32> {
-        resampler::resample_to_16khz_mono(&raw, self.sample_rate, self.channels)
+    pub fn state(&self) -> CaptureState {
+        if !self.recording {
+            CaptureState::Idle
+        } else if self.interrupted.is_some() {
+            CaptureState::Interrupted
+        } else if self.paused.load(Ordering::Relaxed) {
+            CaptureState::Paused
+        } else {
+            CaptureState::Recording
+        }
     }
 
-    #[allow(dead_code)]
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
 
-    #[allow(dead_code)]
     pub fn channels(&self) -> u16 {
         self.channels
     }
+
+    /// Take the fault reported by the stream's error callback, if any.
+    pub fn take_fault(&self) -> Option<StreamFault> {
+        self.fault.lock().unwrap().take()
+    }
+
+    /// Handle a fault reported by the stream. When the device is lost during
+    /// a recording, the audio captured so far is kept and, if failover is
+    /// enabled, capture continues on the default device. Returns the name of
+    /// the device the stream was reopened on, or an error if capture could
+    /// not continue.
+    pub fn recover(&mut self, fault: &StreamFault) -> Result<Option<String>> {
+        if !fault.device_lost || self.stream.is_none() {
+            return Ok(None);
+        }
+        self.stream.take();
+
+        if !self.recording {
+            self.close();
+            return match self.rearm() {
+                Some(name) => Ok(Some(name)),
+                None => Err(anyhow!("Could not reopen the pre-roll stream")),
+            };
+        }
+
+        let mut recording = self.detach()?;
+        if !self.failover {
+            self.interrupted = Some(recording);
+            return Err(anyhow!("Input device lost, recording interrupted"));
+        }
+
+        let preroll_ms = self.armed.as_ref().map_or(0, |armed| armed.preroll_ms);
+        match self.open(&None, &CaptureConfig::default(), preroll_ms) {
+            Ok(name) => {
+                recording.retarget(self.sample_rate, self.channels);
+                if let Some(consumer) = &self.consumer {
+                    consumer.send(ConsumerCommand::Begin(recording))?;
+                }
+                log::warn!("Input device lost, recording continues on '{}'", name);
+                Ok(Some(name))
+            }
+            Err(e) => {
+                self.interrupted = Some(recording);
+                Err(e.context("Input device lost and the default device could not be opened"))
+            }
+        }
+    }
+
+    /// Reopen the armed stream with its original settings. `find_device`
+    /// falls back to the default device if the armed one is gone.
+    fn rearm(&mut self) -> Option<String> {
+        let armed = self.armed.clone()?;
+        match self.open(&armed.device_name, &armed.capture, armed.preroll_ms) {
+            Ok(name) => Some(name),
+            Err(e) => {
+                log::error!("Failed to reopen the pre-roll stream: {}", e);
+                self.armed = None;
+                None
+            }
+        }
+    }
+
+    /// Build and start the input stream plus its consumer thread, returning
+    /// the name of the device that was opened.
+    fn open(
+        &mut self,
+        device_name: &Option<String>,
+        capture: &CaptureConfig,
+        preroll_ms: u32,
+    ) -> Result<String> {
+        let device = find_device(device_name)?;
+        let name = device.name().unwrap_or_default();
+
+        // A config for the requested device may not apply to the fallback
+        let capture = if device_name.as_deref() == Some(name.as_str()) {
+            capture::resolve(&device, capture)?
+        } else {
+            capture::resolve(&device, &CaptureConfig::default())?
+        };
+        self.sample_rate = capture.sample_rate();
+        self.channels = capture.channels();
+
+        let capacity = self.sample_rate as usize * self.channels as usize * RING_BUFFER_SECONDS;
+        let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+
+        self.fault.lock().unwrap().take();
+        let shared = CallbackShared {
+            paused: self.paused.clone(),
+            overruns: self.overruns.clone(),
+            level: self.level.clone(),
+            fault: self.fault.clone(),
+        };
+        let stream = build_stream(&device, &capture, producer, shared)?;
+
+        let preroll = PreRollBuffer::new(preroll_ms, self.sample_rate, self.channels);
+        self.consumer = Some(spawn_consumer(consumer, preroll, self.level.clone())?);
+
+        stream.play()?;
+        self.stream = Some(stream);
+
+        log::info!(
+            "Input stream opened on '{}': {}Hz, {} of {} channels, {}",
+            name,
+            self.sample_rate,
+            self.channels,
+            capture.stream_config.channels,
+            capture.sample_format
+        );
+        Ok(name)
+    }
+
+    /// Close the stream and wait for the consumer thread to exit.
+    fn close(&mut self) {
+        self.stream.take();
+        if let Some(consumer) = self.consumer.take() {
+            consumer.shutdown();
+        }
+    }
+
+    /// Stop the consumer thread and take over its recording, with everything
+    /// still in the ring buffer processed.
+    fn detach(&mut self) -> Result<Box<ActiveRecording>> {
+        let consumer = self
+            .consumer
+            .take()
+            .ok_or_else(|| anyhow!("Not recording"))?;
+        let (reply, rx) = mpsc::channel();
+        consumer.send(ConsumerCommand::Detach(reply))?;
+        let recording = rx.recv();
+        consumer.shutdown();
+        recording
+            .ok()
+            .flatten()
+            .ok_or_else(|| anyhow!("Audio consumer thread stopped"))
+    }
+}
+
+/// Flags and counters shared with the stream callbacks.
+struct CallbackShared {
+    paused: Arc<AtomicBool>,
+    overruns: Arc<OverrunCounter>,
+    level: Arc<SharedLevel>,
+    fault: Arc<Mutex<Option<StreamFault>>>,
+}
+
+/// Build an input stream in the resolved sample format.
+fn build_stream(
+    device: &cpal::Device,
+    capture: &ResolvedCapture,
+    producer: HeapProd<f32>,
+    shared: CallbackShared,
+) -> Result<Stream> {
+    use cpal::SampleFormat;
+    let stream = match capture.sample_format {
+        SampleFormat::I8 => build_input_stream::<i8>(device, capture, producer, shared)?,
+        SampleFormat::I16 => build_input_stream::<i16>(device, capture, producer, shared)?,
+        SampleFormat::I32 => build_input_stream::<i32>(device, capture, producer, shared)?,
+        SampleFormat::I64 => build_input_stream::<i64>(device, capture, producer, shared)?,
+        SampleFormat::U8 => build_input_stream::<u8>(device, capture, producer, shared)?,
+        SampleFormat::U16 => build_input_stream::<u16>(device, capture, producer, shared)?,
+        SampleFormat::U32 => build_input_stream::<u32>(device, capture, producer, shared)?,
+        SampleFormat::U64 => build_input_stream::<u64>(device, capture, producer, shared)?,
+        SampleFormat::F32 => build_input_stream::<f32>(device, capture, producer, shared)?,
+        SampleFormat::F64 => build_input_stream::<f64>(device, capture, producer, shared)?,
+        format => return Err(anyhow!("Unsupported sample format: {:?}", format)),
+    };
+    Ok(stream)
+}
+
+/// Build an input stream for sample type `T`. The callback never blocks: it
+/// skips data while paused, otherwise keeps the selected channels,
+/// normalizes them to f32 straight into the ring buffer, counts whatever
+/// does not fit and meters the input level. Stream errors are recorded for
+/// the audio engine to act on.
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    capture: &ResolvedCapture,
+    mut producer: HeapProd<f32>,
+    shared: CallbackShared,
+) -> Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let device_channels = capture.stream_config.channels.max(1) as usize;
+    let channel_map = capture.channel_map.clone();
+    let mut meter = LevelMeter::new(capture.sample_rate(), capture.channels());
+    let fault = shared.fault.clone();
+    let stream = device.build_input_stream(
+        &capture.stream_config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            if shared.paused.load(Ordering::Relaxed) {
+                return;
+            }
+            let selected = || select_channels(data, device_channels, &channel_map);
+            let expected = data.len() / device_channels * channel_map.len();
+            let pushed = producer.push_iter(selected());
+            if pushed < expected {
+                shared.overruns.events.fetch_add(1, Ordering::Relaxed);
+                shared
+                    .overruns
+                    .samples
+                    .fetch_add((expected - pushed) as u64, Ordering::Relaxed);
+            }
+            if let Some(level) = meter.process(selected()) {
+                shared.level.publish(level);
+            }
+        },
+        move |err| {
+            log::error!("Audio stream error: {}", err);
+            let mut slot = fault.lock().unwrap();
+            // Keep a lost device over any later, less severe error
+            if !slot.as_ref().is_some_and(|f| f.device_lost) {
+                *slot = Some(StreamFault {
+                    message: err.to_string(),
+                    device_lost: matches!(err, cpal::StreamError::DeviceNotAvailable),
+                });
+            }
+        },
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// The `channel_map` channels of each interleaved frame of `data`, in map
+/// order, normalized to f32. A trailing partial frame is skipped.
+fn select_channels<'a, T>(
+    data: &'a [T],
+    device_channels: usize,
+    channel_map: &'a [usize],
+) -> impl Iterator<Item = f32> + 'a
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    data.chunks_exact(device_channels).flat_map(move |frame| {
+        channel_map
+            .iter()
+            .map(move |&c| frame[c].to_sample::<f32>())
+    })
+}
+
+/// Per-recording processing state owned by the consumer thread.
+struct ActiveRecording {
+    quality: ResampleQuality,
+    resampler: StreamingResampler,
+    vad: Option<(VoiceActivityDetector, OnSilence)>,
+    max_duration: Option<DurationWatch>,
+    on_level: Option<OnLevel>,
+    on_audio: Option<OnAudio>,
+    audio: RecordingBuffer,
+    /// Resampler output, moved into `audio` after every block.
+    audio_16k: Vec<f32>,
+}
+
+impl ActiveRecording {
+    fn process(&mut self, samples: &[f32]) {
+        if let Some((detector, on_silence)) = self.vad.as_mut() {
+            if detector.process(samples) {
+                on_silence();
+            }
+        }
+        self.resampler.push(samples, &mut self.audio_16k);
+        if let Some(watch) = self.max_duration.as_mut() {
+            watch.advance(self.audio_16k.len());
+        }
+        self.flush();
+    }
+
+    /// Move the resampler output into the recording.
+    fn flush(&mut self) {
+        if self.audio_16k.is_empty() {
+            return;
+        }
+        if let Some(on_audio) = self.on_audio.as_mut() {
+            on_audio(&self.audio_16k);
+        }
+        self.audio.push(&self.audio_16k);
+        self.audio_16k.clear();
+    }
+
+    fn finish(mut self) -> Result<RecordedAudio> {
+        self.resampler.finish(&mut self.audio_16k);
+        self.flush();
+        self.audio.finish()
+    }
+
+    /// Continue on a stream with a different format, keeping the audio and
+    /// VAD state gathered so far.
+    fn retarget(&mut self, sample_rate: u32, channels: u16) {
+        let resampler = StreamingResampler::new(sample_rate, channels, self.quality);
+        std::mem::replace(&mut self.resampler, resampler).finish(&mut self.audio_16k);
+        self.flush();
+        if let Some((detector, _)) = self.vad.as_mut() {
+            detector.retarget(sample_rate, channels);
+        }
+    }
+}
+
+enum ConsumerCommand {
+    /// Start a recording, seeded with the buffered pre-roll.
+    Begin(Box<ActiveRecording>),
+    /// Drain the ring buffer and reply with the finished 16kHz audio.
+    End(mpsc::Sender<Result<RecordedAudio>>),
+    /// Drain the ring buffer and hand the recording back unfinished, so it
+    /// can continue on another stream.
+    Detach(mpsc::Sender<Option<Box<ActiveRecording>>>),
+}
+
+struct ConsumerHandle {
+    commands: mpsc::Sender<ConsumerCommand>,
+    thread: JoinHandle<()>,
+}
+
+impl ConsumerHandle {
+    fn send(&self, command: ConsumerCommand) -> Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| anyhow!("Audio consumer thread stopped"))
+    }
+
+    fn end(&self) -> Result<RecordedAudio> {
+        let (reply, rx) = mpsc::channel();
+        self.send(ConsumerCommand::End(reply))?;
+        rx.recv()
+            .map_err(|_| anyhow!("Audio consumer thread stopped"))?
+    }
+
+    /// Close the command channel and wait for the thread to exit.
+    fn shutdown(self) {
+        drop(self.commands);
+        if self.thread.join().is_err() {
+            log::error!("Audio consumer thread panicked");
+        }
+    }
+}
+
+/// Drain the ring buffer until the command channel is closed. Between
+/// recordings the samples only feed the pre-roll buffer; during a recording
+/// they are run through VAD and resampled to 16kHz mono, and new level
+/// readings are forwarded to the recording's level callback.
+fn spawn_consumer(
+    mut consumer: HeapCons<f32>,
+    mut preroll: PreRollBuffer,
+    level: Arc<SharedLevel>,
+) -> Result<ConsumerHandle> {
+    let (commands, rx) = mpsc::channel();
+    let thread = std::thread::Builder::new()
+        .name("audio-consumer".into())
+        .spawn(move || {
+            let mut chunk = vec![0.0f32; 4096];
+            let mut active: Option<Box<ActiveRecording>> = None;
+            let mut last_level = 0;
+            loop {
+                match rx.try_recv() {
+                    Ok(ConsumerCommand::Begin(mut recording)) => {
+                        recording.process(&preroll.take());
+                        // Skip the reading published before the recording began
+                        level.read_if_newer(&mut last_level);
+                        active = Some(recording);
+                    }
+                    Ok(ConsumerCommand::End(reply)) => {
+                        // Everything still in the ring was captured before the stop
+                        let audio = active.take().map(|mut recording| {
+                            drain(&mut consumer, &mut chunk, &mut recording);
+                            recording.finish()
+                        });
+                        let _ = reply.send(audio.unwrap_or_else(|| Ok(RecordedAudio::default())));
+                        continue;
+                    }
+                    Ok(ConsumerCommand::Detach(reply)) => {
+                        if let Some(recording) = active.as_mut() {
+                            drain(&mut consumer, &mut chunk, recording);
+                        }
+                        let _ = reply.send(active.take());
+                        continue;
+                    }
+                    Err(mpsc::TryRecvError::Disconnected) => break,
+                    Err(mpsc::TryRecvError::Empty) => {}
+                }
+
+                if let Some(on_level) = active.as_ref().and_then(|r| r.on_level.as_ref()) {
+                    if let Some(reading) = level.read_if_newer(&mut last_level) {
+                        on_level(reading);
+                    }
+                }
+
+                let n = consumer.pop_slice(&mut chunk);
+                if n == 0 {
+                    std::thread::sleep(Duration::from_millis(5));
+                    continue;
+                }
+
+                match active.as_mut() {
+                    Some(recording) => recording.process(&chunk[..n]),
+                    None => preroll.push(&chunk[..n]),
+                }
+            }
+        })?;
+    Ok(ConsumerHandle { commands, thread })
+}
+
+/// Process everything currently in the ring buffer.
+fn drain(consumer: &mut HeapCons<f32>, chunk: &mut [f32], recording: &mut ActiveRecording) {
+    loop {
+        let n = consumer.pop_slice(chunk);
+        if n == 0 {
+            break;
+        }
+        recording.process(&chunk[..n]);
+    }
 }
 
 /// Encode f32 samples as 16-bit PCM WAV bytes.
@@ -181,3 +845,51 @@ pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
     writer.finalize().unwrap();
     cursor.into_inner()
 }
+
+/// Root-mean-square level of a block of samples.
+pub(crate) fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-2, "{} != {}", a, e);
+        }
+    }
+
+    #[test]
+    fn test_select_channels_i16() {
+        // Stereo, left only
+        let data = [i16::MIN, 0, 0, i16::MAX, i16::MAX, i16::MIN];
+        let selected: Vec<f32> = select_channels(&data, 2, &[0]).collect();
+        assert_close(&selected, &[-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_select_channels_u16() {
+        // Unsigned samples are centred on the midpoint
+        let data = [u16::MIN, 1 << 15, u16::MAX];
+        let selected: Vec<f32> = select_channels(&data, 1, &[0]).collect();
+        assert_close(&selected, &[-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_select_channels_f32_multichannel_map() {
+        // Four channels, keep 3 then 1; the partial last frame is skipped
+        let data = [
+            0.0, 0.1, 0.2, 0.3, //
+            0.4, 0.5, 0.6, 0.7, //
+            0.8, 0.9,
+        ];
+        let selected: Vec<f32> = select_channels(&data, 4, &[3, 1]).collect();
+        assert_close(&selected, &[0.3, 0.1, 0.7, 0.5]);
+    }
+}
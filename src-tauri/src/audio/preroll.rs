@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreRollConfig {
+    /// Keep the input stream open between recordings. Off by default: the
+    /// microphone stays live while armed, although pre-roll audio is only
+    /// kept in memory and discarded unless a recording starts.
+    pub enabled: bool,
+    /// How much audio before the hotkey press is prepended to a recording.
+    pub duration_ms: u32,
+}
+
+impl Default for PreRollConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_ms: 500,
+        }
+    }
+}
+
+/// Rolling buffer holding the most recent interleaved device-rate samples
+/// while the recorder is armed. Samples are evicted a whole frame at a time,
+/// so the buffer always starts on a frame boundary.
+pub struct PreRollBuffer {
+    samples: VecDeque<f32>,
+    capacity: usize,
+    channels: usize,
+}
+
+impl PreRollBuffer {
+    pub fn new(duration_ms: u32, sample_rate: u32, channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        let frames = sample_rate as usize * duration_ms as usize / 1000;
+        let capacity = frames * channels;
+        Self {
+            samples: VecDeque::with_capacity(capacity + channels),
+            capacity,
+            channels,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.samples.extend(samples);
+        // A trailing partial frame does not count towards the capacity
+        let complete = self.samples.len() - self.samples.len() % self.channels;
+        if complete > self.capacity {
+            self.samples.drain(..complete - self.capacity);
+        }
+    }
+
+    /// Remove and return everything buffered, oldest first.
+    pub fn take(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_only_the_most_recent_samples() {
+        // 10ms at 1kHz mono = 10 samples
+        let mut buffer = PreRollBuffer::new(10, 1000, 1);
+        let input: Vec<f32> = (0..25).map(|i| i as f32).collect();
+        buffer.push(&input);
+
+        let expected: Vec<f32> = (15..25).map(|i| i as f32).collect();
+        assert_eq!(buffer.take(), expected);
+        assert!(buffer.take().is_empty());
+    }
+
+    #[test]
+    fn test_evicts_whole_frames() {
+        // 2 frames of stereo, pushed in chunks that split frames
+        let mut buffer = PreRollBuffer::new(2, 1000, 2);
+        buffer.push(&[0.0, 0.1, 1.0]);
+        buffer.push(&[1.1, 2.0, 2.1, 3.0]);
+
+        let kept = buffer.take();
+        // The trailing half frame is kept so the live samples continue it
+        assert_eq!(kept, vec![1.0, 1.1, 2.0, 2.1, 3.0]);
+    }
+
+    #[test]
+    fn test_zero_duration_buffers_nothing() {
+        let mut buffer = PreRollBuffer::new(0, 48000, 2);
+        buffer.push(&[0.5; 128]);
+        assert!(buffer.take().is_empty());
+    }
+}
@@ -0,0 +1,531 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Spill files hold the recording as it is handed to the providers.
+const SPILL_RATE: u32 = 16000;
+
+/// Once spilling, buffered audio is written out and the WAV header updated
+/// at least this often, so a crash loses at most this much.
+const FLUSH_SAMPLES: usize = SPILL_RATE as usize;
+
+/// Subdirectory of the spill directory holding recordings left behind by a
+/// previous run.
+const RECOVERED_DIR: &str = "recovered";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpillConfig {
+    /// Move long recordings out of memory into a temporary file.
+    pub enabled: bool,
+    /// Seconds of audio kept in memory before the recording spills to disk.
+    pub threshold_secs: u32,
+}
+
+impl Default for SpillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_secs: 300,
+        }
+    }
+}
+
+/// Where and when a recording spills, passed in [`super::RecordingOptions`].
+#[derive(Debug, Clone)]
+pub struct SpillTarget {
+    pub dir: PathBuf,
+    pub threshold_secs: u32,
+}
+
+/// A recording left on disk by a run that did not finish it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredRecording {
+    /// File name inside the recovered directory, used to refer to it.
+    pub name: String,
+    pub duration_ms: u64,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// 16kHz mono audio of a finished recording. A spill file is deleted when
+/// this is dropped, so it survives until the recording has been handled and
+/// is picked up by [`recover_orphans`] if the app dies first. One that
+/// could not be transcribed is kept with [`RecordedAudio::keep_for_recovery`].
+pub enum RecordedAudio {
+    Memory(Vec<f32>),
+    File { path: PathBuf, samples: usize },
+}
+
+impl RecordedAudio {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Memory(audio) => audio.len(),
+            Self::File { samples, .. } => *samples,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read the samples back a part at a time, from disk if spilled.
+    pub fn reader(&self) -> Result<AudioReader<'_>> {
+        match self {
+            Self::Memory(audio) => Ok(AudioReader::Memory(audio)),
+            Self::File { path, samples } => AudioReader::open(path, *samples),
+        }
+    }
+
+    /// Move a spill file into the recovered directory instead of deleting
+    /// it, so a recording that failed to transcribe is offered again like
+    /// one left by a crash. `false` if the recording was in memory.
+    pub fn keep_for_recovery(&mut self) -> Result<bool> {
+        let Self::File { path, .. } = self else {
+            return Ok(false);
+        };
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(anyhow!("Not a spill file: {}", path.display()));
+        };
+        let recovered = dir.join(RECOVERED_DIR);
+        fs::create_dir_all(&recovered)?;
+        fs::rename(&*path, recovered.join(name))
+            .with_context(|| format!("Failed to keep {}", path.display()))?;
+        *self = Self::default();
+        Ok(true)
+    }
+}
+
+impl Default for RecordedAudio {
+    fn default() -> Self {
+        Self::Memory(Vec::new())
+    }
+}
+
+impl Drop for RecordedAudio {
+    fn drop(&mut self) {
+        if let Self::File { path, .. } = self {
+            if let Err(e) = fs::remove_file(&*path) {
+                log::warn!("Failed to remove spill file {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Reads 16kHz mono audio in parts, so a spilled recording is never
+/// loaded whole.
+pub enum AudioReader<'a> {
+    Memory(&'a [f32]),
+    File {
+        reader: hound::WavReader<BufReader<File>>,
+        /// Samples not read yet
+        left: usize,
+    },
+}
+
+impl AudioReader<'_> {
+    /// Read up to `samples` samples of a WAV file; anything past that was
+    /// never flushed.
+    fn open(path: &Path, samples: usize) -> Result<Self> {
+        let reader = hound::WavReader::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        Ok(Self::File {
+            reader,
+            left: samples,
+        })
+    }
+
+    /// Samples not read yet.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Memory(audio) => audio.len(),
+            Self::File { left, .. } => *left,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read the next `max` samples or as many as are left.
+    pub fn read(&mut self, max: usize) -> Result<Vec<f32>> {
+        match self {
+            Self::Memory(audio) => {
+                let (part, rest) = audio.split_at(max.min(audio.len()));
+                *audio = rest;
+                Ok(part.to_vec())
+            }
+            Self::File { reader, left } => {
+                let wanted = max.min(*left);
+                let part = reader
+                    .samples::<i16>()
+                    .take(wanted)
+                    .map(|s| Ok(s? as f32 / i16::MAX as f32))
+                    .collect::<Result<Vec<f32>>>()?;
+                // A file cut short has nothing more to give
+                *left = if part.len() < wanted {
+                    0
+                } else {
+                    *left - wanted
+                };
+                Ok(part)
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a [f32]> for AudioReader<'a> {
+    fn from(audio: &'a [f32]) -> Self {
+        Self::Memory(audio)
+    }
+}
+
+/// Open spill file. Samples are only counted as kept once flushed, because
+/// the header is updated at the same time.
+struct SpillWriter {
+    path: PathBuf,
+    writer: hound::WavWriter<BufWriter<File>>,
+    flushed: usize,
+}
+
+impl SpillWriter {
+    fn create(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        // Recordings started within the same second must not share a file
+        let name = format!(
+            "recording-{}-{}.wav",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        let path = dir.join(name);
+        let writer = hound::WavWriter::create(&path, wav_spec())
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Self {
+            path,
+            writer,
+            flushed: 0,
+        })
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        for &sample in samples {
+            self.writer.write_sample(to_i16(sample))?;
+        }
+        self.writer.flush()?;
+        self.flushed += samples.len();
+        Ok(())
+    }
+}
+
+/// Collects a recording's 16kHz audio on the consumer thread. It is kept
+/// in memory up to the spill threshold; past it everything is moved to a
+/// WAV file and only about a second of audio is buffered at a time.
+pub struct RecordingBuffer {
+    memory: Vec<f32>,
+    target: Option<SpillTarget>,
+    file: Option<SpillWriter>,
+    /// Set once spilling fails; the rest of the recording stays in memory.
+    failed: bool,
+}
+
+impl RecordingBuffer {
+    pub fn new(target: Option<SpillTarget>) -> Self {
+        Self {
+            memory: Vec::new(),
+            target,
+            file: None,
+            failed: false,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        self.memory.extend_from_slice(samples);
+        let limit = match (&self.file, &self.target) {
+            (Some(_), _) => FLUSH_SAMPLES,
+            (None, Some(target)) => target.threshold_secs as usize * SPILL_RATE as usize,
+            (None, None) => return,
+        };
+        if self.failed || self.memory.len() < limit {
+            return;
+        }
+        if let Err(e) = self.spill() {
+            log::error!("Failed to spill recording to disk: {:#}", e);
+            self.failed = true;
+        }
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                let dir = &self
+                    .target
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("No spill target"))?
+                    .dir;
+                let file = SpillWriter::create(dir)?;
+                log::info!("Long recording, spilling to {}", file.path.display());
+                self.file.insert(file)
+            }
+        };
+        file.write(&self.memory)?;
+        self.memory.clear();
+        self.memory.shrink_to(FLUSH_SAMPLES * 2);
+        Ok(())
+    }
+
+    /// Write out what is still buffered and close the spill file, if any.
+    pub fn finish(mut self) -> Result<RecordedAudio> {
+        let Some(mut file) = self.file.take() else {
+            return Ok(RecordedAudio::Memory(self.memory));
+        };
+        if !self.failed && file.write(&self.memory).is_ok() {
+            let samples = file.flushed;
+            file.writer.finalize()?;
+            return Ok(RecordedAudio::File {
+                path: file.path,
+                samples,
+            });
+        }
+
+        // Whatever did not make it to disk is still in memory
+        let SpillWriter {
+            path,
+            writer,
+            flushed,
+        } = file;
+        drop(writer);
+        let mut audio = read_wav(&path, flushed)?;
+        audio.append(&mut self.memory);
+        let _ = fs::remove_file(&path);
+        Ok(RecordedAudio::Memory(audio))
+    }
+}
+
+/// Move spill files left in `dir` by a previous run into the recovered
+/// directory. Must only be called while nothing is recording.
+pub fn recover_orphans(dir: &Path) -> Result<usize> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    let recovered = dir.join(RECOVERED_DIR);
+    let mut count = 0;
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if !is_spill_file(&path) {
+            continue;
+        }
+        // A crash before the first flush leaves only a header
+        if wav_len(&path).unwrap_or(0) == 0 {
+            let _ = fs::remove_file(&path);
+            continue;
+        }
+        fs::create_dir_all(&recovered)?;
+        if let Some(name) = path.file_name() {
+            fs::rename(&path, recovered.join(name))?;
+            count += 1;
+        }
+    }
+    if count > 0 {
+        log::warn!("Recovered {} unfinished recordings", count);
+    }
+    Ok(count)
+}
+
+/// Recordings waiting in the recovered directory, newest first.
+pub fn list_recovered(dir: &Path) -> Vec<RecoveredRecording> {
+    let Ok(entries) = fs::read_dir(dir.join(RECOVERED_DIR)) else {
+        return Vec::new();
+    };
+    let mut recordings: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| is_spill_file(path))
+        .filter_map(|path| {
+            let samples = wav_len(&path).ok()?;
+            let recorded_at = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some(RecoveredRecording {
+                name: path.file_name()?.to_string_lossy().into_owned(),
+                duration_ms: samples as u64 * 1000 / SPILL_RATE as u64,
+                recorded_at: recorded_at.into(),
+            })
+        })
+        .collect();
+    recordings.sort_by_key(|r| std::cmp::Reverse(r.recorded_at));
+    recordings
+}
+
+/// Open a recovered recording to read as 16kHz mono.
+pub fn open_recovered(dir: &Path, name: &str) -> Result<AudioReader<'static>> {
+    let path = recovered_path(dir, name)?;
+    AudioReader::open(&path, wav_len(&path)?)
+}
+
+pub fn discard_recovered(dir: &Path, name: &str) -> Result<()> {
+    fs::remove_file(recovered_path(dir, name)?)?;
+    Ok(())
+}
+
+/// Resolve a recovered recording by name, refusing anything that is not a
+/// spill file directly inside the recovered directory.
+fn recovered_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let path = dir.join(RECOVERED_DIR).join(name);
+    if Path::new(name).file_name() != Some(name.as_ref()) || !is_spill_file(&path) {
+        return Err(anyhow!("Not a recovered recording: {}", name));
+    }
+    Ok(path)
+}
+
+fn is_spill_file(path: &Path) -> bool {
+    path.is_file()
+        && path.extension().is_some_and(|ext| ext == "wav")
+        && path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("recording-"))
+}
+
+fn wav_spec() -> hound::WavSpec {
+    hound::WavSpec {
+        channels: 1,
+        sample_rate: SPILL_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+fn wav_len(path: &Path) -> Result<usize> {
+    Ok(hound::WavReader::open(path)?.len() as usize)
+}
+
+fn read_wav(path: &Path, samples: usize) -> Result<Vec<f32>> {
+    AudioReader::open(path, samples)?.read(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spill-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn target(dir: &Path) -> Option<SpillTarget> {
+        Some(SpillTarget {
+            dir: dir.to_path_buf(),
+            threshold_secs: 1,
+        })
+    }
+
+    #[test]
+    fn test_short_recordings_stay_in_memory() {
+        let dir = temp_dir("memory");
+        let mut buffer = RecordingBuffer::new(target(&dir));
+        buffer.push(&[0.25; 8000]);
+        let audio = buffer.finish().unwrap();
+        assert!(matches!(audio, RecordedAudio::Memory(_)));
+        assert_eq!(audio.len(), 8000);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_spills_past_threshold_and_bounds_memory() {
+        let dir = temp_dir("spill");
+        let mut buffer = RecordingBuffer::new(target(&dir));
+        for _ in 0..10 {
+            buffer.push(&[0.5; 4000]);
+        }
+        assert!(buffer.file.is_some());
+        assert!(buffer.memory.len() < FLUSH_SAMPLES);
+
+        let audio = buffer.finish().unwrap();
+        let RecordedAudio::File { path, .. } = &audio else {
+            panic!("recording was not spilled");
+        };
+        let path = path.clone();
+        assert_eq!(audio.len(), 40000);
+
+        // Read back in parts
+        let mut reader = audio.reader().unwrap();
+        let mut samples = Vec::new();
+        while !reader.is_empty() {
+            let part = reader.read(16000).unwrap();
+            assert!(part.len() <= 16000);
+            samples.extend(part);
+        }
+        drop(reader);
+        assert_eq!(samples.len(), 40000);
+        assert!(samples.iter().all(|s| (s - 0.5).abs() < 1e-3));
+
+        drop(audio);
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_spill_files_are_unique() {
+        let dir = temp_dir("unique");
+        let first = SpillWriter::create(&dir).unwrap();
+        let second = SpillWriter::create(&dir).unwrap();
+        assert_ne!(first.path, second.path);
+        drop((first, second));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unfinished_spill_is_recovered() {
+        let dir = temp_dir("recover");
+        let mut buffer = RecordingBuffer::new(target(&dir));
+        buffer.push(&[0.1; 20000]);
+        // Simulate a crash: the buffer is never finished
+        std::mem::forget(buffer);
+
+        assert_eq!(recover_orphans(&dir).unwrap(), 1);
+        let recovered = list_recovered(&dir);
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].duration_ms, 1250);
+
+        let mut audio = open_recovered(&dir, &recovered[0].name).unwrap();
+        assert_eq!(audio.read(usize::MAX).unwrap().len(), 20000);
+        assert!(audio.is_empty());
+        drop(audio);
+        assert!(open_recovered(&dir, "../recording-x.wav").is_err());
+
+        discard_recovered(&dir, &recovered[0].name).unwrap();
+        assert!(list_recovered(&dir).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failed_recording_is_kept_for_recovery() {
+        let dir = temp_dir("keep");
+        let mut buffer = RecordingBuffer::new(target(&dir));
+        buffer.push(&[0.2; 24000]);
+        let mut audio = buffer.finish().unwrap();
+        let RecordedAudio::File { path, .. } = &audio else {
+            panic!("recording was not spilled");
+        };
+        let path = path.clone();
+
+        assert!(audio.keep_for_recovery().unwrap());
+        drop(audio);
+        assert!(!path.exists());
+
+        let recovered = list_recovered(&dir);
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].duration_ms, 1500);
+        let mut reader = open_recovered(&dir, &recovered[0].name).unwrap();
+        assert_eq!(reader.read(usize::MAX).unwrap().len(), 24000);
+        drop(reader);
+
+        let mut memory = RecordedAudio::Memory(vec![0.0; 100]);
+        assert!(!memory.keep_for_recovery().unwrap());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use super::rms;
+
+/// Thresholds for stripping silence before audio is sent to a provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SilenceTrimConfig {
+    pub enabled: bool,
+    /// RMS level (0.0..1.0) below which a frame counts as silence.
+    pub threshold: f32,
+    /// Silence kept before the first and after the last voiced frame.
+    pub padding_ms: u32,
+    /// Internal pauses longer than this are shortened to this length.
+    pub max_pause_ms: u32,
+    /// Analysis frame length.
+    pub frame_ms: u32,
+}
+
+impl Default for SilenceTrimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 0.01,
+            padding_ms: 200,
+            max_pause_ms: 700,
+            frame_ms: 10,
+        }
+    }
+}
+
+/// The parts of mono `samples` kept once leading and trailing silence is
+/// stripped and long internal pauses are collapsed, in order. Empty if no
+/// frame is above the threshold.
+fn kept_ranges(samples: &[f32], sample_rate: u32, config: &SilenceTrimConfig) -> Vec<Range<usize>> {
+    let frame_ms = config.frame_ms.max(1);
+    let frame_len = ((sample_rate as u64 * frame_ms as u64) / 1000).max(1) as usize;
+
+    let voiced: Vec<bool> = samples
+        .chunks(frame_len)
+        .map(|frame| rms(frame) >= config.threshold)
+        .collect();
+
+    let (first, last) = match (
+        voiced.iter().position(|&v| v),
+        voiced.iter().rposition(|&v| v),
+    ) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Vec::new(),
+    };
+
+    let pad_frames = config.padding_ms.div_ceil(frame_ms) as usize;
+    let max_pause_frames = (config.max_pause_ms / frame_ms) as usize;
+    let start = first.saturating_sub(pad_frames);
+    let end = (last + 1 + pad_frames).min(voiced.len());
+
+    let frame_range = |from: usize, to: usize| {
+        let lo = from * frame_len;
+        let hi = (to * frame_len).min(samples.len());
+        lo..hi
+    };
+
+    let mut kept: Vec<Range<usize>> = Vec::new();
+    let mut keep = |range: Range<usize>| match kept.last_mut() {
+        Some(previous) if previous.end == range.start => previous.end = range.end,
+        _ if range.is_empty() => {}
+        _ => kept.push(range),
+    };
+    let mut i = start;
+    while i < end {
+        if voiced[i] || i < first || i > last {
+            keep(frame_range(i, i + 1));
+            i += 1;
+            continue;
+        }
+
+        // Internal pause: keep its edges so words don't run together
+        let run_end = (i..=last).find(|&j| voiced[j]).unwrap_or(last);
+        if run_end - i > max_pause_frames {
+            let head = max_pause_frames / 2;
+            let tail = max_pause_frames - head;
+            keep(frame_range(i, i + head));
+            keep(frame_range(run_end - tail, run_end));
+        } else {
+            keep(frame_range(i, run_end));
+        }
+        i = run_end;
+    }
+
+    kept
+}
+
+/// Where the audio left after trimming was in the original, so times in
+/// a transcript of the trimmed audio can be mapped back.
+#[derive(Debug, Clone)]
+pub struct TrimMap {
+    kept: Vec<Range<usize>>,
+    len: usize,
+}
+
+impl TrimMap {
+    /// Milliseconds removed by trimming.
+    pub fn removed_ms(&self) -> u64 {
+        let kept: usize = self.kept.iter().map(|range| range.len()).sum();
+        (self.len - kept) as u64 * 1000 / 16000
+    }
+
+    /// Position in the original audio of `ms` into the trimmed audio.
+    pub fn original_ms(&self, ms: u64) -> u64 {
+        let mut position = (ms * 16000 / 1000) as usize;
+        for range in &self.kept {
+            if position < range.len() {
+                return (range.start + position) as u64 * 1000 / 16000;
+            }
+            position -= range.len();
+        }
+        // Past the end of the trimmed audio
+        let end = self.kept.last().map_or(0, |range| range.end);
+        (end + position) as u64 * 1000 / 16000
+    }
+}
+
+/// Apply `config` to 16 kHz mono audio, returning the trimmed buffer and
+/// where it came from. A disabled config passes the audio through.
+pub fn trim_for_upload(audio: Vec<f32>, config: &SilenceTrimConfig) -> (Vec<f32>, TrimMap) {
+    let len = audio.len();
+    if !config.enabled {
+        let kept = std::iter::once(0..len).collect();
+        return (audio, TrimMap { kept, len });
+    }
+    let kept = kept_ranges(&audio, 16000, config);
+    let trimmed = kept
+        .iter()
+        .flat_map(|range| &audio[range.clone()])
+        .copied()
+        .collect();
+    (trimmed, TrimMap { kept, len })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(ms: u32) -> Vec<f32> {
+        let n = (RATE * ms / 1000) as usize;
+        (0..n)
+            .map(|i| 0.3 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn silence(ms: u32) -> Vec<f32> {
+        vec![0.0; (RATE * ms / 1000) as usize]
+    }
+
+    fn ms(samples: &[f32]) -> u32 {
+        (samples.len() as u64 * 1000 / RATE as u64) as u32
+    }
+
+    #[test]
+    fn test_strips_edges_keeping_padding() {
+        let config = SilenceTrimConfig::default();
+        let mut input = silence(2000);
+        input.extend(tone(1000));
+        input.extend(silence(3000));
+
+        let (output, _) = trim_for_upload(input, &config);
+        assert_eq!(ms(&output), 1000 + 2 * config.padding_ms);
+    }
+
+    #[test]
+    fn test_collapses_long_internal_pause() {
+        let config = SilenceTrimConfig::default();
+        let mut input = tone(500);
+        input.extend(silence(5000));
+        input.extend(tone(500));
+
+        let (output, _) = trim_for_upload(input, &config);
+        assert_eq!(ms(&output), 1000 + config.max_pause_ms);
+    }
+
+    #[test]
+    fn test_keeps_short_internal_pause() {
+        let config = SilenceTrimConfig::default();
+        let mut input = tone(500);
+        input.extend(silence(300));
+        input.extend(tone(500));
+
+        let len = input.len();
+        let (output, _) = trim_for_upload(input, &config);
+        assert_eq!(output.len(), len);
+    }
+
+    #[test]
+    fn test_maps_times_back_to_original() {
+        let config = SilenceTrimConfig::default();
+        let mut input = silence(1000);
+        input.extend(tone(500));
+        input.extend(silence(5000));
+        input.extend(tone(500));
+
+        let (output, map) = trim_for_upload(input, &config);
+        assert_eq!(map.removed_ms() as u32, 7000 - ms(&output));
+        // The first tone starts after the padding
+        assert_eq!(map.original_ms(config.padding_ms as u64), 1000);
+        // The second tone starts after the collapsed pause
+        let second = (config.padding_ms + 500 + config.max_pause_ms) as u64;
+        assert_eq!(map.original_ms(second), 6500);
+    }
+
+    #[test]
+    fn test_all_silence_is_empty() {
+        let config = SilenceTrimConfig::default();
+        let (output, map) = trim_for_upload(silence(1000), &config);
+        assert!(output.is_empty());
+        assert_eq!(map.removed_ms(), 1000);
+        assert!(trim_for_upload(Vec::new(), &config).0.is_empty());
+    }
+}
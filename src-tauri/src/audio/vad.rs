@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+
+use super::rms;
+
+/// Tuning for the energy/zero-crossing voice activity detector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    /// RMS level (0.0..1.0) above which a frame counts as speech.
+    pub energy_threshold: f32,
+    /// Zero-crossing rate above which a quieter frame still counts as speech
+    /// (catches unvoiced consonants like "s" and "f").
+    pub zcr_threshold: f32,
+    /// Trailing silence after speech that ends the recording.
+    pub silence_timeout_ms: u32,
+    /// Amount of speech required before the silence timer is armed.
+    pub min_speech_ms: u32,
+    /// Analysis frame length.
+    pub frame_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            energy_threshold: 0.01,
+            zcr_threshold: 0.25,
+            silence_timeout_ms: 2000,
+            min_speech_ms: 200,
+            frame_ms: 30,
+        }
+    }
+}
+
+/// Callback-driven auto-stop: the recorder feeds the detector and calls
+/// `on_silence` once when trailing silence exceeds the timeout.
+pub struct AutoStop {
+    pub config: VadConfig,
+    pub on_silence: Box<dyn Fn() + Send + 'static>,
+}
+
+/// Streaming voice activity detector working on interleaved samples.
+///
+/// Needs no model: each frame is classified by RMS energy, with the
+/// zero-crossing rate used to keep low-energy fricatives from counting as
+/// silence.
+pub struct VoiceActivityDetector {
+    config: VadConfig,
+    channels: usize,
+    frame_len: usize,
+    frame_ms: f32,
+    frame: Vec<f32>,
+    pending_sum: f32,
+    pending_channels: usize,
+    speech_ms: f32,
+    silence_ms: f32,
+    triggered: bool,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(config: VadConfig, sample_rate: u32, channels: u16) -> Self {
+        let frame_ms = config.frame_ms.max(1);
+        let frame_len = ((sample_rate as u64 * frame_ms as u64) / 1000).max(1) as usize;
+        Self {
+            frame_ms: frame_ms as f32,
+            config,
+            channels: channels.max(1) as usize,
+            frame_len,
+            frame: Vec::with_capacity(frame_len),
+            pending_sum: 0.0,
+            pending_channels: 0,
+            speech_ms: 0.0,
+            silence_ms: 0.0,
+            triggered: false,
+        }
+    }
+
+    /// Switch to a new input format mid-recording, keeping the speech and
+    /// silence measured so far.
+    pub fn retarget(&mut self, sample_rate: u32, channels: u16) {
+        let fresh = Self::new(self.config.clone(), sample_rate, channels);
+        *self = Self {
+            speech_ms: self.speech_ms,
+            silence_ms: self.silence_ms,
+            triggered: self.triggered,
+            ..fresh
+        };
+    }
+
+    /// Feed interleaved samples. Returns `true` exactly once, on the call
+    /// where trailing silence after speech first exceeds the timeout.
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        if self.triggered {
+            return false;
+        }
+
+        for &sample in samples {
+            // Downmix to mono on the fly; partial frames carry over between calls
+            self.pending_sum += sample;
+            self.pending_channels += 1;
+            if self.pending_channels < self.channels {
+                continue;
+            }
+            self.frame.push(self.pending_sum / self.channels as f32);
+            self.pending_sum = 0.0;
+            self.pending_channels = 0;
+
+            if self.frame.len() == self.frame_len {
+                let is_speech = self.classify_frame();
+                self.frame.clear();
+
+                if is_speech {
+                    self.speech_ms += self.frame_ms;
+                    self.silence_ms = 0.0;
+                } else {
+                    self.silence_ms += self.frame_ms;
+                }
+
+                if self.speech_ms >= self.config.min_speech_ms as f32
+                    && self.silence_ms >= self.config.silence_timeout_ms as f32
+                {
+                    self.triggered = true;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn classify_frame(&self) -> bool {
+        let level = rms(&self.frame);
+        if level >= self.config.energy_threshold {
+            return true;
+        }
+        level >= self.config.energy_threshold * 0.5
+            && zero_crossing_rate(&self.frame) >= self.config.zcr_threshold
+    }
+}
+
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    if frame.len() < 2 {
+        return 0.0;
+    }
+    let crossings = frame
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    crossings as f32 / (frame.len() - 1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(ms: u32, amplitude: f32) -> Vec<f32> {
+        let n = (RATE * ms / 1000) as usize;
+        (0..n)
+            .map(|i| {
+                amplitude * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / RATE as f32).sin()
+            })
+            .collect()
+    }
+
+    fn silence(ms: u32) -> Vec<f32> {
+        vec![0.0; (RATE * ms / 1000) as usize]
+    }
+
+    fn config() -> VadConfig {
+        VadConfig {
+            silence_timeout_ms: 1000,
+            ..VadConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_silence_only_never_triggers() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 1);
+        assert!(!vad.process(&silence(5000)));
+    }
+
+    #[test]
+    fn test_triggers_after_trailing_silence() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 1);
+        assert!(!vad.process(&tone(500, 0.3)));
+        assert!(!vad.process(&silence(900)));
+        assert!(vad.process(&silence(200)));
+        // Fires only once
+        assert!(!vad.process(&silence(2000)));
+    }
+
+    #[test]
+    fn test_speech_resets_silence_timer() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 1);
+        vad.process(&tone(500, 0.3));
+        assert!(!vad.process(&silence(800)));
+        assert!(!vad.process(&tone(300, 0.3)));
+        assert!(!vad.process(&silence(800)));
+        assert!(vad.process(&silence(300)));
+    }
+
+    #[test]
+    fn test_low_noise_counts_as_silence() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 1);
+        vad.process(&tone(500, 0.3));
+        assert!(vad.process(&tone(1100, 0.002)));
+    }
+
+    #[test]
+    fn test_short_click_does_not_arm() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 1);
+        vad.process(&tone(60, 0.5));
+        assert!(!vad.process(&silence(3000)));
+    }
+
+    #[test]
+    fn test_stereo_input_in_small_chunks() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 2);
+        let interleave =
+            |mono: Vec<f32>| -> Vec<f32> { mono.into_iter().flat_map(|s| [s, s]).collect() };
+        let mut audio = interleave(tone(500, 0.3));
+        audio.extend(interleave(silence(1100)));
+
+        // Odd chunk size splits frames mid-way through a channel pair
+        let fired = audio.chunks(333).filter(|c| vad.process(c)).count();
+        assert_eq!(fired, 1);
+    }
+
+    #[test]
+    fn test_retarget_keeps_silence_measured_so_far() {
+        let mut vad = VoiceActivityDetector::new(config(), RATE, 1);
+        vad.process(&tone(500, 0.3));
+        assert!(!vad.process(&silence(600)));
+
+        // Device failover to 48kHz stereo mid-silence
+        vad.retarget(48000, 2);
+        assert!(!vad.process(&vec![0.0; 48 * 2 * 300]));
+        assert!(vad.process(&vec![0.0; 48 * 2 * 200]));
+    }
+}
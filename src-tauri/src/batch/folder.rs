@@ -0,0 +1,278 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::providers::TranscriptionResult;
+
+/// Extensions picked up from a folder, compared case-insensitively.
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "flac", "mp3", "ogg", "oga", "opus"];
+
+/// Folder transcription output and the watched folder.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchConfig {
+    /// Write `<file>.txt`, e.g. `call.mp3.txt`, with the plain transcript.
+    pub write_txt: bool,
+    /// Write `<file>.json` with the transcript and its details.
+    pub write_json: bool,
+    /// Folder whose new audio files are transcribed while the app runs.
+    pub watch_folder: Option<String>,
+    /// How often the watched folder is checked. A file must also have been
+    /// left unchanged this long, so copies still in progress are skipped.
+    pub poll_secs: u32,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            write_txt: true,
+            write_json: true,
+            watch_folder: None,
+            poll_secs: 10,
+        }
+    }
+}
+
+/// One look at a folder.
+#[derive(Debug, Default)]
+pub struct Scan {
+    /// Audio files in the folder.
+    pub total: usize,
+    /// Audio files whose transcripts are up to date.
+    pub done: usize,
+    /// Files to transcribe, with the modification time they were seen at.
+    pub pending: Vec<(PathBuf, SystemTime)>,
+}
+
+/// Find the audio files in `folder` that still need transcripts. A file is
+/// done once every sidecar exists and is newer than it, so an interrupted
+/// batch picks up where it stopped. Files in `failed` are skipped until
+/// they change, and files modified within `settle` are left for later.
+pub fn scan(
+    folder: &Path,
+    config: &BatchConfig,
+    failed: &HashMap<PathBuf, SystemTime>,
+    settle: Duration,
+) -> Result<Scan> {
+    let entries =
+        std::fs::read_dir(folder).with_context(|| format!("Cannot read {}", folder.display()))?;
+
+    let mut scan = Scan::default();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !is_audio(&path) {
+            continue;
+        }
+        let modified = match entry.metadata() {
+            Ok(meta) if meta.is_file() => meta.modified()?,
+            _ => continue,
+        };
+        scan.total += 1;
+
+        if is_transcribed(&path, modified, config) {
+            scan.done += 1;
+        } else if failed.get(&path) != Some(&modified)
+            && modified.elapsed().unwrap_or_default() >= settle
+        {
+            scan.pending.push((path, modified));
+        }
+    }
+    scan.pending.sort();
+    Ok(scan)
+}
+
+fn is_audio(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_none_or(|n| n.starts_with('.') || n.starts_with('~'));
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    !hidden && extension.is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Transcript paths for `audio`; plain text if both formats are off. The
+/// extension is added to the whole file name, so `call.mp3` and `call.wav`
+/// in one folder get separate transcripts.
+fn sidecar_paths(audio: &Path, config: &BatchConfig) -> Vec<PathBuf> {
+    let sidecar = |extension: &str| {
+        let mut name = audio.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(extension);
+        audio.with_file_name(name)
+    };
+    let mut paths = Vec::new();
+    if config.write_txt || !config.write_json {
+        paths.push(sidecar("txt"));
+    }
+    if config.write_json {
+        paths.push(sidecar("json"));
+    }
+    paths
+}
+
+fn is_transcribed(audio: &Path, modified: SystemTime, config: &BatchConfig) -> bool {
+    sidecar_paths(audio, config).iter().all(|path| {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|written| written >= modified)
+    })
+}
+
+/// Contents of the `.json` sidecar.
+#[derive(Serialize)]
+struct Sidecar<'a> {
+    file: &'a str,
+    /// Length of the audio file.
+    audio_ms: u64,
+    transcribed_at: DateTime<Utc>,
+    #[serde(flatten)]
+    result: &'a TranscriptionResult,
+}
+
+/// Write the transcripts for `audio`. Each file is written under a
+/// temporary name and renamed, so a half-written transcript never counts
+/// as done.
+pub fn write_sidecars(
+    audio: &Path,
+    audio_ms: u64,
+    result: &TranscriptionResult,
+    config: &BatchConfig,
+) -> Result<()> {
+    let file = audio
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    for path in sidecar_paths(audio, config) {
+        let contents = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::to_vec_pretty(&Sidecar {
+                file,
+                audio_ms,
+                transcribed_at: Utc::now(),
+                result,
+            })?
+        } else {
+            format!("{}\n", result.text).into_bytes()
+        };
+
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let temp = path.with_file_name(format!(".{}.tmp", name));
+        std::fs::write(&temp, contents)
+            .with_context(|| format!("Cannot write {}", temp.display()))?;
+        std::fs::rename(&temp, &path)
+            .with_context(|| format!("Cannot write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ProviderId;
+
+    fn temp_folder() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("batch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn result(text: &str) -> TranscriptionResult {
+        TranscriptionResult {
+            text: text.into(),
+            provider: ProviderId::OpenAiWhisper,
+            duration_ms: 1200,
+            language: Some("en".into()),
+            trimmed_ms: 0,
+            segments: Vec::new(),
+            words: Vec::new(),
+            confidence: None,
+            alternatives: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_scan_skips_transcribed_and_non_audio() {
+        let dir = temp_folder();
+        for name in ["b.mp3", "a.WAV", "notes.txt", ".hidden.wav", "c.flac"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let config = BatchConfig::default();
+        write_sidecars(&dir.join("c.flac"), 1000, &result("done"), &config).unwrap();
+
+        let scan = scan(&dir, &config, &HashMap::new(), Duration::ZERO).unwrap();
+        let pending: Vec<PathBuf> = scan.pending.into_iter().map(|(p, _)| p).collect();
+        assert_eq!(scan.total, 3);
+        assert_eq!(scan.done, 1);
+        assert_eq!(pending, vec![dir.join("a.WAV"), dir.join("b.mp3")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_skips_failed_and_unsettled_files() {
+        let dir = temp_folder();
+        std::fs::write(dir.join("a.wav"), b"").unwrap();
+        std::fs::write(dir.join("b.wav"), b"").unwrap();
+        let config = BatchConfig::default();
+
+        let modified = std::fs::metadata(dir.join("a.wav"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        let failed = HashMap::from([(dir.join("a.wav"), modified)]);
+        let scan_now = scan(&dir, &config, &failed, Duration::ZERO).unwrap();
+        assert_eq!(scan_now.pending.len(), 1);
+        assert_eq!(scan_now.pending[0].0, dir.join("b.wav"));
+
+        // Just written, so neither has settled yet
+        let settling = scan(&dir, &config, &HashMap::new(), Duration::from_secs(60)).unwrap();
+        assert!(settling.pending.is_empty());
+        assert_eq!(settling.total, 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sidecars_hold_transcript() {
+        let dir = temp_folder();
+        let audio = dir.join("call.mp3");
+        std::fs::write(&audio, b"").unwrap();
+        let config = BatchConfig::default();
+        write_sidecars(&audio, 61000, &result("Hello there."), &config).unwrap();
+
+        let txt = std::fs::read_to_string(dir.join("call.mp3.txt")).unwrap();
+        assert_eq!(txt, "Hello there.\n");
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.join("call.mp3.json")).unwrap()).unwrap();
+        assert_eq!(json["file"], "call.mp3");
+        assert_eq!(json["audio_ms"], 61000);
+        assert_eq!(json["text"], "Hello there.");
+        assert_eq!(json["language"], "en");
+
+        // Only the transcripts and the audio are left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_same_stem_files_keep_separate_transcripts() {
+        let dir = temp_folder();
+        for name in ["call.mp3", "call.wav"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let config = BatchConfig::default();
+        write_sidecars(&dir.join("call.mp3"), 1000, &result("mp3"), &config).unwrap();
+
+        let scan = scan(&dir, &config, &HashMap::new(), Duration::ZERO).unwrap();
+        assert_eq!(scan.done, 1);
+        assert_eq!(scan.pending.len(), 1);
+        assert_eq!(scan.pending[0].0, dir.join("call.wav"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+pub mod folder;
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::audio::resampler::ResampleQuality;
+use crate::providers::TranscriptionResult;
+use crate::state::AppState;
+use folder::BatchConfig;
+
+/// Emitted to the frontend as `batch-progress` whenever a job moves on.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchProgress {
+    pub folder: String,
+    /// Whether this is the watched folder rather than a one-off run.
+    pub watching: bool,
+    pub total: usize,
+    pub done: usize,
+    pub failed: usize,
+    /// File being transcribed.
+    pub current: Option<String>,
+    pub last_error: Option<String>,
+    /// Every file was transcribed or failed, or the job was cancelled. A
+    /// watch job only finishes when it is stopped.
+    pub finished: bool,
+}
+
+/// A folder being transcribed in the background.
+pub struct BatchJob {
+    pub folder: PathBuf,
+    task: JoinHandle<()>,
+    progress: Arc<Mutex<BatchProgress>>,
+}
+
+impl BatchJob {
+    pub fn spawn(app: &AppHandle, folder: PathBuf, watching: bool) -> Self {
+        let progress = Arc::new(Mutex::new(BatchProgress {
+            folder: folder.display().to_string(),
+            watching,
+            ..Default::default()
+        }));
+        let task = tauri::async_runtime::spawn(run(
+            app.clone(),
+            folder.clone(),
+            watching,
+            progress.clone(),
+        ));
+        Self {
+            folder,
+            task,
+            progress,
+        }
+    }
+
+    pub fn progress(&self) -> BatchProgress {
+        self.progress.lock().unwrap().clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress.lock().unwrap().finished
+    }
+
+    /// Stop the job. A file being transcribed is abandoned; its transcript
+    /// is not written, so the next run does it again.
+    pub fn cancel(self, app: &AppHandle) {
+        self.task.abort();
+        let mut progress = self.progress.lock().unwrap();
+        progress.current = None;
+        progress.finished = true;
+        let _ = app.emit("batch-progress", &*progress);
+    }
+}
+
+/// The one-off folder job and the watched folder job, at most one each.
+#[derive(Default)]
+pub struct BatchJobs {
+    pub folder: Option<BatchJob>,
+    pub watch: Option<BatchJob>,
+}
+
+/// Start, restart or stop watching to match `batch.watch_folder`.
+pub fn apply_watch(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let folder = state
+        .settings
+        .lock()
+        .unwrap()
+        .batch
+        .watch_folder
+        .clone()
+        .map(PathBuf::from);
+
+    let mut jobs = state.batch.lock().unwrap();
+    if jobs.watch.as_ref().map(|job| &job.folder) == folder.as_ref() {
+        return;
+    }
+    if let Some(job) = jobs.watch.take() {
+        job.cancel(app);
+    }
+    if let Some(folder) = folder {
+        log::info!("Watching {} for audio files", folder.display());
+        jobs.watch = Some(BatchJob::spawn(app, folder, true));
+    }
+}
+
+async fn run(app: AppHandle, dir: PathBuf, watching: bool, progress: Arc<Mutex<BatchProgress>>) {
+    let update = |change: &dyn Fn(&mut BatchProgress)| {
+        let mut progress = progress.lock().unwrap();
+        change(&mut progress);
+        let _ = app.emit("batch-progress", &*progress);
+    };
+
+    let mut failed: HashMap<PathBuf, SystemTime> = HashMap::new();
+    loop {
+        let (config, quality) = {
+            let state = app.state::<AppState>();
+            let settings = state.settings.lock().unwrap();
+            (settings.batch.clone(), settings.resample_quality)
+        };
+        let poll = Duration::from_secs(config.poll_secs.max(1) as u64);
+        let settle = if watching { poll } else { Duration::ZERO };
+
+        match folder::scan(&dir, &config, &failed, settle) {
+            Ok(scan) => {
+                update(&|p| {
+                    p.total = scan.total;
+                    p.done = scan.done;
+                    p.failed = failed.len();
+                });
+                for (path, modified) in scan.pending {
+                    let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+                    update(&|p| p.current = name.clone());
+
+                    let outcome = transcribe_file(&app, &path, &config, quality).await;
+                    if let Err(e) = &outcome {
+                        log::error!("Failed to transcribe {}: {}", path.display(), e);
+                        failed.insert(path.clone(), modified);
+                    }
+                    update(&|p| {
+                        p.current = None;
+                        match &outcome {
+                            Ok(()) => p.done += 1,
+                            Err(e) => {
+                                p.failed = failed.len();
+                                p.last_error =
+                                    Some(format!("{}: {}", name.as_deref().unwrap_or("?"), e));
+                            }
+                        }
+                    });
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to scan {}: {}", dir.display(), e);
+                update(&|p| p.last_error = Some(e.to_string()));
+            }
+        }
+
+        if !watching {
+            break;
+        }
+        tokio::time::sleep(poll).await;
+    }
+    update(&|p| p.finished = true);
+}
+
+/// Decode, transcribe and write the transcripts for one file.
+async fn transcribe_file(
+    app: &AppHandle,
+    path: &Path,
+    config: &BatchConfig,
+    quality: ResampleQuality,
+) -> Result<()> {
+    let audio_16k = {
+        let path = path.to_path_buf();
+        tauri::async_runtime::spawn_blocking(move || {
+            crate::audio::decode::decode_file(&path, quality)
+        })
+        .await??
+    };
+    let audio_ms = audio_16k.len() as u64 * 1000 / 16000;
+
+    let state = app.state::<AppState>();
+    let provider = state.provider_manager.lock().unwrap().get_active().id();
+    // A file without speech still gets an (empty) transcript, so it is done
+    let result = state
+        .transcribe(audio_16k.as_slice().into())
+        .await?
+        .unwrap_or(TranscriptionResult {
+            text: String::new(),
+            provider,
+            duration_ms: 0,
+            language: None,
+            trimmed_ms: audio_ms,
+            segments: Vec::new(),
+            words: Vec::new(),
+            confidence: None,
+            alternatives: Vec::new(),
+        });
+    folder::write_sidecars(path, audio_ms, &result, config)
+}
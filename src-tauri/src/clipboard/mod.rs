@@ -4,11 +4,16 @@ use enigo::{Direction, Enigo, Key, Keyboard, Settings};
 use std::thread;
 use std::time::Duration;
 
-/// Copy text to clipboard and simulate a paste keystroke.
-pub fn paste_text(text: &str) -> Result<()> {
-    // Set text to clipboard
+/// Copy text to clipboard.
+pub fn copy_text(text: &str) -> Result<()> {
     let mut clipboard = Clipboard::new()?;
     clipboard.set_text(text)?;
+    Ok(())
+}
+
+/// Copy text to clipboard and simulate a paste keystroke.
+pub fn paste_text(text: &str) -> Result<()> {
+    copy_text(text)?;
 
     // Small delay to ensure clipboard is ready
     thread::sleep(Duration::from_millis(100));
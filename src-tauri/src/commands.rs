@@ -1,8 +1,16 @@
-use tauri::{AppHandle, Emitter, State};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 
+use crate::audio::capture::DeviceCapabilities;
+use crate::audio::diagnostics::DeviceTestReport;
+use crate::audio::encode::AudioEncoding;
+use crate::audio::engine::CaptureState;
+use crate::audio::spill::{self, AudioReader, RecoveredRecording};
 use crate::audio::AudioDevice;
+use crate::batch::{BatchJob, BatchProgress};
 use crate::history::TranscriptionEntry;
-use crate::providers::ProviderInfo;
+use crate::providers::{ProviderInfo, TranscriptionResult};
 use crate::settings::AppSettings;
 use crate::state::AppState;
 
@@ -11,73 +19,104 @@ pub fn list_input_devices() -> Result<Vec<AudioDevice>, String> {
     Ok(crate::audio::list_input_devices())
 }
 
+/// Sample rates, channels and buffer sizes a device supports.
+#[tauri::command]
+pub fn get_input_capabilities(device_name: Option<String>) -> Result<DeviceCapabilities, String> {
+    crate::audio::capture::capabilities(&device_name).map_err(|e| e.to_string())
+}
+
+/// Record a few seconds from a device and report whether it is suitable.
+#[tauri::command]
+pub async fn test_input_device(
+    device_name: Option<String>,
+    duration_ms: Option<u64>,
+) -> Result<DeviceTestReport, String> {
+    let duration = Duration::from_millis(duration_ms.unwrap_or(3000).clamp(1000, 10000));
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::audio::diagnostics::test_input_device(&device_name, duration)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn start_recording(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    {
-        let device_name = {
-            let settings = state.settings.lock().map_err(|e| e.to_string())?;
-            settings.input_device.clone()
-        };
-        let mut recorder = state.recorder.lock().map_err(|e| e.to_string())?;
-        recorder.start(&device_name).map_err(|e| e.to_string())?;
-    }
+    let mut options = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        crate::recording_options(&app, &settings)
+    };
+    options.on_audio = crate::live_transcription(&app);
+    state
+        .recorder
+        .start(options)
+        .await
+        .map_err(|e| e.to_string())?;
     *state.is_recording.lock().unwrap() = true;
     let _ = app.emit("recording-started", ());
     Ok(())
 }
 
+/// Stop capturing without ending the recording; `stop` still transcribes
+/// everything recorded before and after the pause.
+#[tauri::command]
+pub async fn pause_recording(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    state.recorder.pause().await.map_err(|e| e.to_string())?;
+    let _ = app.emit("recording-paused", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_recording(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    state.recorder.resume().await.map_err(|e| e.to_string())?;
+    let _ = app.emit("recording-resumed", ());
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn stop_recording_and_transcribe(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    // Stop recording and get raw audio
-    let raw_audio = {
-        let mut recorder = state.recorder.lock().map_err(|e| e.to_string())?;
-        recorder.stop().map_err(|e| e.to_string())?
-    };
+    // Stop recording; the audio is already 16kHz mono
+    let recording = state.recorder.stop().await.map_err(|e| {
+        state.abort_live_transcription();
+        e.to_string()
+    })?;
     *state.is_recording.lock().unwrap() = false;
-    let _ = app.emit("recording-stopped", ());
+    let _ = app.emit("recording-stopped", &recording.stats);
+
+    // A spilled recording's file is removed once `audio` is dropped, unless
+    // it is kept because transcription failed
+    let audio = recording.audio;
 
-    if raw_audio.is_empty() {
+    if audio.is_empty() {
+        state.abort_live_transcription();
         return Err("No audio recorded".into());
     }
 
     let _ = app.emit("transcribing", ());
 
-    // Resample to 16kHz mono
-    let audio_16k = {
-        let recorder = state.recorder.lock().unwrap();
-        recorder.get_audio_16khz_mono(raw_audio)
-    };
-
-    // Get provider and config (drop locks before await)
-    let (provider, config) = {
-        let pm = state.provider_manager.lock().unwrap();
-        let settings = state.settings.lock().unwrap();
-        let provider = pm.get_active(); // returns Arc, safe across await
-        let config = settings.get_provider_config(&provider.id());
-        (provider, config)
-    };
-
-    // Transcribe (no locks held)
-    let result = provider
-        .transcribe(&audio_16k, &config)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // Auto-paste if enabled
-    {
-        let settings = state.settings.lock().unwrap();
-        if settings.auto_paste && !result.text.is_empty() {
-            if let Err(e) = crate::clipboard::paste_text(&result.text) {
-                log::error!("Auto-paste failed: {}", e);
+    // Speech transcribed while recording is not sent again
+    let result = match state.finish_live_transcription().await {
+        Some(live) => Some(live),
+        None => {
+            let transcribed = match audio.reader() {
+                Ok(reader) => state.transcribe(reader).await,
+                Err(e) => Err(e),
+            };
+            match transcribed {
+                Ok(result) => result,
+                Err(e) => return Err(format!("{}.{}", e, crate::keep_failed_recording(audio))),
             }
         }
-    }
+    };
+    let result = result.ok_or("No speech detected")?;
+
+    crate::auto_paste(&app, &result);
 
     // Add to history
     {
@@ -90,9 +129,123 @@ pub async fn stop_recording_and_transcribe(
     Ok(result.text)
 }
 
+/// Recordings a previous run spilled to disk but never finished.
+#[tauri::command]
+pub fn list_recovered_recordings(app: AppHandle) -> Result<Vec<RecoveredRecording>, String> {
+    Ok(crate::recordings_dir(&app)
+        .map(|dir| spill::list_recovered(&dir))
+        .unwrap_or_default())
+}
+
+/// Transcribe a recovered recording into the history, then delete it.
+#[tauri::command]
+pub async fn transcribe_recovered_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<String, String> {
+    let dir = crate::recordings_dir(&app).ok_or("No app data directory")?;
+    let reader = spill::open_recovered(&dir, &name).map_err(|e| e.to_string())?;
+
+    let result = transcribe_into_history(&app, &state, reader).await?;
+    spill::discard_recovered(&dir, &name).map_err(|e| e.to_string())?;
+    Ok(result.text)
+}
+
+/// Transcribe an audio file (WAV, FLAC, MP3 or Ogg) into the history.
 #[tauri::command]
-pub fn get_recording_state(state: State<'_, AppState>) -> Result<bool, String> {
-    Ok(*state.is_recording.lock().unwrap())
+pub async fn transcribe_file(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<String, String> {
+    let quality = state.settings.lock().unwrap().resample_quality;
+    let audio_16k = tauri::async_runtime::spawn_blocking(move || {
+        crate::audio::decode::decode_file(Path::new(&path), quality)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let result = transcribe_into_history(&app, &state, audio_16k.as_slice().into()).await?;
+    Ok(result.text)
+}
+
+/// Transcribe every audio file in `folder` in the background, writing the
+/// transcripts next to them. Files transcribed by an earlier run are skipped.
+#[tauri::command]
+pub fn start_batch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    folder: String,
+) -> Result<(), String> {
+    let folder = PathBuf::from(folder);
+    if !folder.is_dir() {
+        return Err(format!("{} is not a folder", folder.display()));
+    }
+
+    let mut jobs = state.batch.lock().unwrap();
+    if jobs.folder.as_ref().is_some_and(|job| !job.is_finished()) {
+        return Err("A folder is already being transcribed".into());
+    }
+    if jobs.watch.as_ref().is_some_and(|job| job.folder == folder) {
+        return Err("This folder is already being watched".into());
+    }
+    jobs.folder = Some(BatchJob::spawn(&app, folder, false));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_batch(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    if let Some(job) = state.batch.lock().unwrap().folder.take() {
+        job.cancel(&app);
+    }
+    Ok(())
+}
+
+/// Progress of the folder job, if one has run, and the watched folder.
+#[tauri::command]
+pub fn get_batch_status(state: State<'_, AppState>) -> Result<Vec<BatchProgress>, String> {
+    let jobs = state.batch.lock().unwrap();
+    Ok(jobs
+        .folder
+        .iter()
+        .chain(&jobs.watch)
+        .map(BatchJob::progress)
+        .collect())
+}
+
+/// Transcribe audio that was not just recorded and add it to the history.
+/// Nothing is pasted: whatever had focus when it was recorded is long gone.
+async fn transcribe_into_history(
+    app: &AppHandle,
+    state: &AppState,
+    audio: AudioReader<'_>,
+) -> Result<TranscriptionResult, String> {
+    let result = state
+        .transcribe(audio)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("No speech detected")?;
+
+    {
+        let mut history = state.history.lock().unwrap();
+        history.add(&result);
+    }
+    let _ = app.emit("transcription-complete", &result);
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn discard_recovered_recording(app: AppHandle, name: String) -> Result<(), String> {
+    let dir = crate::recordings_dir(&app).ok_or("No app data directory")?;
+    spill::discard_recovered(&dir, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_recording_state(state: State<'_, AppState>) -> Result<bool, String> {
+    let status = state.recorder.status().await.map_err(|e| e.to_string())?;
+    Ok(status.state != CaptureState::Idle)
 }
 
 #[tauri::command]
@@ -106,6 +259,25 @@ pub async fn save_settings(
     state: State<'_, AppState>,
     settings: AppSettings,
 ) -> Result<(), String> {
+    // Reject capture formats the device cannot open
+    for (device_name, config) in &settings.device_configs {
+        crate::audio::capture::validate(device_name, config)
+            .map_err(|e| format!("{}: {}", device_name, e))?;
+    }
+    for (provider, config) in &settings.provider_configs {
+        if !config.encoding.is_available() {
+            return Err(format!(
+                "{}: Ogg/Opus upload needs a build with the ogg-opus feature",
+                provider
+            ));
+        }
+    }
+    if let Some(folder) = &settings.batch.watch_folder {
+        if !Path::new(folder).is_dir() {
+            return Err(format!("{} is not a folder", folder));
+        }
+    }
+
     // Update provider manager's active provider
     {
         let mut pm = state.provider_manager.lock().unwrap();
@@ -116,9 +288,29 @@ pub async fn save_settings(
     settings.save(&app).map_err(|e| e.to_string())?;
 
     // Update in-memory settings
-    {
+    let (rearm, pause_hotkey_change, rewatch) = {
         let mut current = state.settings.lock().unwrap();
+        let rearm = current.preroll != settings.preroll
+            || current.input_device != settings.input_device
+            || current.get_capture_config() != settings.get_capture_config();
+        let pause_hotkey_change = (current.pause_hotkey != settings.pause_hotkey)
+            .then(|| (current.pause_hotkey.clone(), settings.pause_hotkey.clone()));
+        let rewatch = current.batch.watch_folder != settings.batch.watch_folder;
         *current = settings;
+        (rearm, pause_hotkey_change, rewatch)
+    };
+
+    if let Some((previous, hotkey)) = pause_hotkey_change {
+        crate::register_pause_hotkey(&app, &previous, &hotkey);
+    }
+
+    if rewatch {
+        crate::batch::apply_watch(&app);
+    }
+
+    // Reopen or close the pre-roll stream if its settings changed
+    if rearm {
+        crate::apply_preroll(&app).await;
     }
 
     Ok(())
@@ -135,6 +327,33 @@ pub fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+/// Copy a transcription, or one of its alternatives, held back from
+/// auto-paste.
+#[tauri::command]
+pub fn copy_to_clipboard(text: String) -> Result<(), String> {
+    crate::clipboard::copy_text(&text).map_err(|e| e.to_string())
+}
+
+/// Paste a reading the user confirmed after auto-paste held it back. The
+/// window is hidden first so focus goes back to the app being dictated
+/// into, then given a moment to get there.
+#[tauri::command]
+pub async fn paste_confirmed(app: AppHandle, text: String) -> Result<(), String> {
+    if let Some(win) = app.get_webview_window("main") {
+        win.hide().map_err(|e| e.to_string())?;
+    }
+    #[cfg(target_os = "macos")]
+    let _ = app.hide();
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    crate::clipboard::paste_text(&text).map_err(|e| e.to_string())
+}
+
+/// Upload formats this build can encode.
+#[tauri::command]
+pub fn get_audio_encodings() -> Result<Vec<AudioEncoding>, String> {
+    Ok(AudioEncoding::available())
+}
+
 #[tauri::command]
 pub fn get_providers(state: State<'_, AppState>) -> Result<Vec<ProviderInfo>, String> {
     Ok(state.provider_manager.lock().unwrap().list_providers())
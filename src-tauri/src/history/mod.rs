@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::providers::{ProviderId, TranscriptionResult};
+use crate::providers::{ProviderId, Segment, TranscriptionResult, Word};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionEntry {
@@ -12,6 +12,14 @@ pub struct TranscriptionEntry {
     pub timestamp: DateTime<Utc>,
     pub duration_ms: u64,
     pub language: Option<String>,
+    #[serde(default)]
+    pub trimmed_ms: u64,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub words: Vec<Word>,
+    #[serde(default)]
+    pub confidence: Option<f32>,
 }
 
 pub struct TranscriptionHistory {
@@ -35,6 +43,10 @@ impl TranscriptionHistory {
             timestamp: Utc::now(),
             duration_ms: result.duration_ms,
             language: result.language.clone(),
+            trimmed_ms: result.trimmed_ms,
+            segments: result.segments.clone(),
+            words: result.words.clone(),
+            confidence: result.confidence,
         };
         self.entries.insert(0, entry);
         self.entries.truncate(self.max_entries);
@@ -1,4 +1,5 @@
 mod audio;
+mod batch;
 mod clipboard;
 mod commands;
 mod history;
@@ -7,22 +8,45 @@ mod settings;
 mod state;
 mod tray;
 
+use audio::engine::{AudioEngine, CaptureState};
+use audio::level::AudioLevel;
+use audio::limit::DurationLimit;
+use audio::spill::{RecordedAudio, SpillTarget};
+use audio::vad::AutoStop;
+use audio::{OnAudio, RecordingOptions};
+use providers::streaming::Hypothesis;
+use providers::TranscriptionResult;
 use settings::{AppSettings, InteractionMode};
 use state::AppState;
-use tauri::{Emitter, Manager};
+use std::path::PathBuf;
+use tauri::{Emitter, Listener, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use tokio::sync::mpsc;
 
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_dialog::init())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_handler(|app, _shortcut, event| {
+                .with_handler(|app, shortcut, event| {
                     let state = app.state::<AppState>();
-                    let interaction_mode = {
+                    let (interaction_mode, is_pause_hotkey) = {
                         let settings = state.settings.lock().unwrap();
-                        settings.interaction_mode.clone()
+                        (
+                            settings.interaction_mode.clone(),
+                            matches_hotkey(&settings.pause_hotkey, shortcut),
+                        )
                     };
 
+                    if is_pause_hotkey {
+                        use tauri_plugin_global_shortcut::ShortcutState;
+                        if event.state == ShortcutState::Pressed {
+                            handle_toggle_pause(app);
+                        }
+                        return;
+                    }
+
                     match interaction_mode {
                         InteractionMode::PushToTalk => {
                             use tauri_plugin_global_shortcut::ShortcutState;
@@ -38,13 +62,7 @@ pub fn run() {
                         InteractionMode::Toggle => {
                             use tauri_plugin_global_shortcut::ShortcutState;
                             if event.state == ShortcutState::Pressed {
-                                let is_recording =
-                                    *state.is_recording.lock().unwrap();
-                                if is_recording {
-                                    handle_stop_recording(app);
-                                } else {
-                                    handle_start_recording(app);
-                                }
+                                control_recorder(app, RecorderControl::Toggle);
                             }
                         }
                     }
@@ -57,16 +75,80 @@ pub fn run() {
 
             // Register global hotkey
             let hotkey = settings.hotkey.clone();
+            let pause_hotkey = settings.pause_hotkey.clone();
+
+            // Set aside recordings a previous run spilled but never finished
+            if let Some(dir) = recordings_dir(app.handle()) {
+                if let Err(e) = audio::spill::recover_orphans(&dir) {
+                    log::error!("Failed to recover unfinished recordings: {}", e);
+                }
+            }
 
-            // Initialize app state
-            let app_state = AppState::new(settings);
+            // Starts and stops are carried out in the order they are asked for
+            let (queue, controls) = mpsc::unbounded_channel();
+            app.manage(RecorderQueue(queue));
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(run_recorder_queue(handle, controls));
+
+            // Initialize app state; stream errors go to the frontend
+            let handle = app.handle().clone();
+            let recorder = AudioEngine::spawn(move |event| {
+                let _ = handle.emit("stream-error", &event);
+                if event.interrupted {
+                    // Transcribe what was captured before the device was lost
+                    handle_stop_recording(&handle);
+                }
+                let handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    update_armed_indicator(&handle).await;
+                });
+            });
+            let app_state = AppState::new(settings, recorder);
             app.manage(app_state);
 
             // Setup system tray
             tray::setup_tray(app.handle())?;
 
+            // Resume watching the folder from last time
+            batch::apply_watch(app.handle());
+
+            // Open the pre-roll stream if the user opted in
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                apply_preroll(&handle).await;
+            });
+
+            // Push device hot-plug changes to the frontend
+            let handle = app.handle().clone();
+            if let Err(e) = audio::watch_input_devices(move |devices| {
+                let _ = handle.emit("input-devices-changed", devices);
+            }) {
+                log::error!("Failed to start input device watcher: {}", e);
+            }
+
+            // VAD auto-stop goes through the same path as the hotkey
+            let handle = app.handle().clone();
+            app.listen("silence-detected", move |_| {
+                let state = handle.state::<AppState>();
+                let is_recording = *state.is_recording.lock().unwrap();
+                if is_recording {
+                    log::info!("Trailing silence detected, stopping recording");
+                    handle_stop_recording(&handle);
+                }
+            });
+
+            // Hitting the maximum length stops like the hotkey, in any mode
+            let handle = app.handle().clone();
+            app.listen("max-duration-reached", move |_| {
+                let state = handle.state::<AppState>();
+                let is_recording = *state.is_recording.lock().unwrap();
+                if is_recording {
+                    log::info!("Maximum recording length reached, stopping recording");
+                    handle_stop_recording(&handle);
+                }
+            });
+
             // Register the hotkey
-            use tauri_plugin_global_shortcut::GlobalShortcutExt;
             if let Err(e) = app
                 .handle()
                 .global_shortcut()
@@ -76,32 +158,94 @@ pub fn run() {
             {
                 log::error!("Failed to register hotkey '{}': {}", hotkey, e);
             }
+            register_pause_hotkey(app.handle(), &None, &pause_hotkey);
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::start_recording,
             commands::stop_recording_and_transcribe,
+            commands::pause_recording,
+            commands::resume_recording,
             commands::get_recording_state,
             commands::get_settings,
             commands::save_settings,
             commands::get_history,
             commands::clear_history,
+            commands::copy_to_clipboard,
+            commands::paste_confirmed,
             commands::get_providers,
+            commands::get_audio_encodings,
             commands::list_input_devices,
+            commands::test_input_device,
+            commands::get_input_capabilities,
+            commands::list_recovered_recordings,
+            commands::transcribe_recovered_recording,
+            commands::transcribe_file,
+            commands::start_batch,
+            commands::cancel_batch,
+            commands::get_batch_status,
+            commands::discard_recovered_recording,
         ])
         .run(tauri::generate_context!())
         .expect("error running whisper application");
 }
 
+/// What the hotkeys and automatic stops ask of the recorder.
+#[derive(Debug, Clone, Copy)]
+enum RecorderControl {
+    Start,
+    Stop,
+    /// Stop if recording, start otherwise.
+    Toggle,
+    /// Pause if recording, resume if paused.
+    TogglePause,
+}
+
+/// Sends to the task that carries out [`RecorderControl`]s one at a time.
+/// Handled in separate tasks, the stop of a quick push-to-talk tap could
+/// reach the recorder before its start, leaving the microphone open.
+struct RecorderQueue(mpsc::UnboundedSender<RecorderControl>);
+
+fn control_recorder(app: &tauri::AppHandle, control: RecorderControl) {
+    if let Err(e) = app.state::<RecorderQueue>().0.send(control) {
+        log::error!("Failed to queue {:?}: {}", e.0, e);
+    }
+}
+
+async fn run_recorder_queue(
+    app: tauri::AppHandle,
+    mut controls: mpsc::UnboundedReceiver<RecorderControl>,
+) {
+    while let Some(control) = controls.recv().await {
+        match control {
+            RecorderControl::Start => start_recording(&app).await,
+            RecorderControl::Stop => stop_recording(&app).await,
+            RecorderControl::Toggle => {
+                let is_recording = *app.state::<AppState>().is_recording.lock().unwrap();
+                if is_recording {
+                    stop_recording(&app).await;
+                } else {
+                    start_recording(&app).await;
+                }
+            }
+            RecorderControl::TogglePause => toggle_pause(&app).await,
+        }
+    }
+}
+
 fn handle_start_recording(app: &tauri::AppHandle) {
+    control_recorder(app, RecorderControl::Start);
+}
+
+async fn start_recording(app: &tauri::AppHandle) {
     let state = app.state::<AppState>();
-    let device_name = {
+    let mut options = {
         let settings = state.settings.lock().unwrap();
-        settings.input_device.clone()
+        recording_options(app, &settings)
     };
-    let mut recorder = state.recorder.lock().unwrap();
-    if let Err(e) = recorder.start(&device_name) {
+    options.on_audio = live_transcription(app);
+    if let Err(e) = state.recorder.start(options).await {
         log::error!("Failed to start recording: {}", e);
         let _ = app.emit("error", format!("Failed to start recording: {}", e));
         return;
@@ -110,64 +254,313 @@ fn handle_start_recording(app: &tauri::AppHandle) {
     let _ = app.emit("recording-started", ());
 }
 
-fn handle_stop_recording(app: &tauri::AppHandle) {
-    let app_handle = app.clone();
+/// Whether `shortcut` is the configured `hotkey`.
+fn matches_hotkey(hotkey: &Option<String>, shortcut: &Shortcut) -> bool {
+    hotkey
+        .as_deref()
+        .and_then(|h| h.parse::<Shortcut>().ok())
+        .is_some_and(|h| h.id() == shortcut.id())
+}
+
+/// Swap the registered pause hotkey for a new one. Presses are dispatched
+/// by the handler set in the plugin builder.
+pub(crate) fn register_pause_hotkey(
+    app: &tauri::AppHandle,
+    previous: &Option<String>,
+    hotkey: &Option<String>,
+) {
+    if let Some(previous) = previous {
+        if let Err(e) = app.global_shortcut().unregister(previous.as_str()) {
+            log::warn!("Failed to unregister pause hotkey '{}': {}", previous, e);
+        }
+    }
+    if let Some(hotkey) = hotkey {
+        if let Err(e) = app.global_shortcut().register(hotkey.as_str()) {
+            log::error!("Failed to register pause hotkey '{}': {}", hotkey, e);
+        }
+    }
+}
+
+/// Pause the current recording, or resume it if it is paused.
+fn handle_toggle_pause(app: &tauri::AppHandle) {
+    control_recorder(app, RecorderControl::TogglePause);
+}
+
+async fn toggle_pause(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let result = match state.recorder.status().await.map(|s| s.state) {
+        Ok(CaptureState::Recording) => state.recorder.pause().await.map(|_| "recording-paused"),
+        Ok(CaptureState::Paused) => state.recorder.resume().await.map(|_| "recording-resumed"),
+        Ok(_) => return,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(event) => {
+            let _ = app.emit(event, ());
+        }
+        Err(e) => {
+            log::error!("Failed to pause or resume recording: {}", e);
+            let _ = app.emit("error", format!("Failed to pause recording: {}", e));
+        }
+    }
+}
+
+/// Build the recorder options for a new recording from the current settings.
+pub(crate) fn recording_options(
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+) -> RecordingOptions {
+    RecordingOptions {
+        device_name: settings.input_device.clone(),
+        capture: settings.get_capture_config(),
+        resample_quality: settings.resample_quality,
+        auto_stop: vad_auto_stop(app, settings),
+        max_duration: max_duration_limit(app, settings),
+        on_level: Some(level_events(app)),
+        on_audio: None,
+        failover: settings.device_failover,
+        spill: spill_target(app, settings),
+    }
+}
+
+/// Start transcribing a new recording while it is captured, if enabled.
+/// Hypotheses are emitted as `partial-transcription` events. Returns the
+/// hook that feeds the recorded audio in; the transcript is collected on
+/// stop by [`AppState::finish_live_transcription`].
+pub(crate) fn live_transcription(app: &tauri::AppHandle) -> Option<OnAudio> {
+    let state = app.state::<AppState>();
+    let (provider, config, streaming) = {
+        let pm = state.provider_manager.lock().unwrap();
+        let settings = state.settings.lock().unwrap();
+        let provider = pm.get_active();
+        let config = settings.get_provider_config(&provider.id());
+        (provider, config, settings.streaming.clone())
+    };
+    if !streaming.enabled {
+        return None;
+    }
 
-    // Spawn async task for transcription
+    let (audio_tx, audio_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (hypothesis_tx, mut hypothesis_rx) = tokio::sync::mpsc::unbounded_channel::<Hypothesis>();
+    let task = tauri::async_runtime::spawn(async move {
+        provider
+            .transcribe_stream(audio_rx, hypothesis_tx, &config, &streaming)
+            .await
+    });
+    let events = app.clone();
     tauri::async_runtime::spawn(async move {
-        let state = app_handle.state::<AppState>();
+        while let Some(hypothesis) = hypothesis_rx.recv().await {
+            let _ = events.emit("partial-transcription", hypothesis);
+        }
+    });
 
-        // Stop recording
-        let raw_audio = {
-            let mut recorder = state.recorder.lock().unwrap();
-            match recorder.stop() {
-                Ok(audio) => audio,
-                Err(e) => {
-                    log::error!("Failed to stop recording: {}", e);
-                    let _ = app_handle.emit("error", format!("Failed to stop recording: {}", e));
-                    return;
-                }
-            }
-        };
-        *state.is_recording.lock().unwrap() = false;
-        let _ = app_handle.emit("recording-stopped", ());
+    // A recording that failed to start leaves its task behind
+    if let Some(previous) = state.live.lock().unwrap().replace(task) {
+        previous.abort();
+    }
+    Some(Box::new(move |chunk| {
+        let _ = audio_tx.send(chunk.to_vec());
+    }))
+}
+
+/// Paste a finished transcription if auto-paste is on. A transcription
+/// below the confidence threshold is held back instead: the window is
+/// brought up and `low-confidence` emitted, so the user can pick the
+/// reading to paste with [`commands::paste_confirmed`].
+pub(crate) fn auto_paste(app: &tauri::AppHandle, result: &TranscriptionResult) {
+    let state = app.state::<AppState>();
+    let (paste, confirm) = {
+        let settings = state.settings.lock().unwrap();
+        (
+            settings.auto_paste && !result.text.is_empty(),
+            settings.confidence.needs_confirmation(result),
+        )
+    };
+    if !paste {
+        return;
+    }
+
+    if confirm {
+        log::info!(
+            "Holding back paste of a transcription with confidence {:?}",
+            result.confidence
+        );
+        if let Some(win) = app.get_webview_window("main") {
+            let _ = win.show();
+            let _ = win.unminimize();
+            let _ = win.set_focus();
+        }
+        let _ = app.emit("low-confidence", result);
+    } else if let Err(e) = crate::clipboard::paste_text(&result.text) {
+        log::error!("Auto-paste failed: {}", e);
+    }
+}
+
+/// Directory long recordings are spilled to while they are captured.
+pub(crate) fn recordings_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join("recordings"))
+}
+
+/// Keep a spilled recording that could not be transcribed among the
+/// recovered recordings, so it can be tried again from History. Returns a
+/// note to append to the error shown to the user.
+pub(crate) fn keep_failed_recording(mut audio: RecordedAudio) -> &'static str {
+    match audio.keep_for_recovery() {
+        Ok(true) => " The recording was kept and can be transcribed again from History.",
+        Ok(false) => "",
+        Err(e) => {
+            log::error!("Failed to keep recording: {}", e);
+            ""
+        }
+    }
+}
+
+fn spill_target(app: &tauri::AppHandle, settings: &AppSettings) -> Option<SpillTarget> {
+    if !settings.spill.enabled {
+        return None;
+    }
+    Some(SpillTarget {
+        dir: recordings_dir(app)?,
+        threshold_secs: settings.spill.threshold_secs,
+    })
+}
+
+/// Arm or disarm the pre-roll stream to match the current settings and
+/// update the tray indicator.
+pub(crate) async fn apply_preroll(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let (preroll, device_name, capture) = {
+        let settings = state.settings.lock().unwrap();
+        (
+            settings.preroll.clone(),
+            settings.input_device.clone(),
+            settings.get_capture_config(),
+        )
+    };
+
+    let result = if preroll.enabled {
+        state
+            .recorder
+            .arm(device_name, capture, preroll.duration_ms)
+            .await
+    } else {
+        state.recorder.disarm().await
+    };
+    if let Err(e) = result {
+        log::error!("Failed to update pre-roll: {}", e);
+        let _ = app.emit("error", format!("Failed to update pre-roll: {}", e));
+    }
+
+    update_armed_indicator(app).await;
+}
+
+/// Show in the tray whether the pre-roll stream is open.
+async fn update_armed_indicator(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let armed = state
+        .recorder
+        .status()
+        .await
+        .map(|status| status.armed)
+        .unwrap_or(false);
+    tray::set_armed(app, armed);
+}
+
+/// Forward input level readings to the frontend as `audio-level` events.
+fn level_events(app: &tauri::AppHandle) -> Box<dyn Fn(AudioLevel) + Send + 'static> {
+    let app = app.clone();
+    Box::new(move |level| {
+        let _ = app.emit("audio-level", level);
+    })
+}
+
+/// Build the VAD auto-stop hook for a new recording, if enabled. The hook
+/// emits `silence-detected`, which the listener registered in `setup` turns
+/// into a regular stop. Push-to-talk already stops on release, so VAD only
+/// applies in Toggle mode.
+fn vad_auto_stop(app: &tauri::AppHandle, settings: &AppSettings) -> Option<AutoStop> {
+    if !settings.vad_auto_stop || settings.interaction_mode != InteractionMode::Toggle {
+        return None;
+    }
+    let app = app.clone();
+    Some(AutoStop {
+        config: settings.vad.clone(),
+        on_silence: Box::new(move || {
+            let _ = app.emit("silence-detected", ());
+        }),
+    })
+}
+
+/// Build the maximum length hooks for a new recording, if enabled. Reaching
+/// the limit emits `max-duration-reached`, which the listener registered in
+/// `setup` turns into a regular stop.
+fn max_duration_limit(app: &tauri::AppHandle, settings: &AppSettings) -> Option<DurationLimit> {
+    if !settings.max_duration.enabled {
+        return None;
+    }
+    let (warning_app, limit_app) = (app.clone(), app.clone());
+    Some(DurationLimit {
+        config: settings.max_duration.clone(),
+        on_warning: Box::new(move |warning| {
+            let _ = warning_app.emit("max-duration-warning", warning);
+        }),
+        on_limit: Box::new(move || {
+            let _ = limit_app.emit("max-duration-reached", ());
+        }),
+    })
+}
+
+fn handle_stop_recording(app: &tauri::AppHandle) {
+    control_recorder(app, RecorderControl::Stop);
+}
+
+/// Stop recording, then transcribe in a task of its own so the next
+/// recording can start meanwhile.
+async fn stop_recording(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
 
-        if raw_audio.is_empty() {
-            let _ = app_handle.emit("error", "No audio recorded".to_string());
+    // Stop recording
+    let recording = match state.recorder.stop().await {
+        Ok(recording) => recording,
+        Err(e) => {
+            log::error!("Failed to stop recording: {}", e);
+            state.abort_live_transcription();
+            let _ = app.emit("error", format!("Failed to stop recording: {}", e));
             return;
         }
+    };
+    *state.is_recording.lock().unwrap() = false;
+    let _ = app.emit("recording-stopped", &recording.stats);
 
-        let _ = app_handle.emit("transcribing", ());
+    // A spilled recording's file is removed once `audio` is dropped, unless
+    // it is kept because transcription failed
+    let audio = recording.audio;
+    if audio.is_empty() {
+        state.abort_live_transcription();
+        let _ = app.emit("error", "No audio recorded".to_string());
+        return;
+    }
 
-        // Resample
-        let audio_16k = {
-            let recorder = state.recorder.lock().unwrap();
-            recorder.get_audio_16khz_mono(raw_audio)
-        };
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        let _ = app_handle.emit("transcribing", ());
 
-        // Get provider and config (drop locks before await)
-        let (provider, config) = {
-            let pm = state.provider_manager.lock().unwrap();
-            let settings = state.settings.lock().unwrap();
-            let provider = pm.get_active(); // returns Arc, safe across await
-            let config = settings.get_provider_config(&provider.id());
-            (provider, config)
+        // Speech transcribed while recording is not sent again
+        let result = match state.finish_live_transcription().await {
+            Some(live) => Ok(Some(live)),
+            None => match audio.reader() {
+                Ok(reader) => state.transcribe(reader).await,
+                Err(e) => Err(e.context("Failed to read recording")),
+            },
         };
 
-        // Transcribe (no locks held)
-        let result = provider.transcribe(&audio_16k, &config).await;
-
         match result {
-            Ok(transcription) => {
-                // Auto-paste
-                {
-                    let settings = state.settings.lock().unwrap();
-                    if settings.auto_paste && !transcription.text.is_empty() {
-                        if let Err(e) = crate::clipboard::paste_text(&transcription.text) {
-                            log::error!("Auto-paste failed: {}", e);
-                        }
-                    }
-                }
+            Ok(Some(transcription)) => {
+                auto_paste(&app_handle, &transcription);
 
                 // Add to history
                 {
@@ -177,9 +570,13 @@ fn handle_stop_recording(app: &tauri::AppHandle) {
 
                 let _ = app_handle.emit("transcription-complete", &transcription);
             }
+            Ok(None) => {
+                let _ = app_handle.emit("error", "No speech detected".to_string());
+            }
             Err(e) => {
                 log::error!("Transcription failed: {}", e);
-                let _ = app_handle.emit("error", format!("Transcription failed: {}", e));
+                let kept = keep_failed_recording(audio);
+                let _ = app_handle.emit("error", format!("Transcription failed: {}.{}", e, kept));
             }
         }
     });
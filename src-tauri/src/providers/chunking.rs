@@ -0,0 +1,462 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::{weighted_confidence, ProviderConfig, Segment, SttProvider, TranscriptionResult, Word};
+use crate::audio::rms;
+use crate::audio::spill::AudioReader;
+
+/// Sample rate of the audio handed to providers.
+const SAMPLE_RATE: u64 = 16000;
+
+/// Analysis frame for finding the quietest point to cut at.
+const FRAME_MS: u64 = 20;
+
+/// A cut is placed at the quietest frame within this much before the chunk
+/// limit, or within the last fifth of the chunk if that is shorter.
+const SEARCH_MS: u64 = 10_000;
+
+/// Longest run of words compared when removing overlap between chunks.
+const MAX_OVERLAP_WORDS: usize = 12;
+
+/// Recordings are read, cleaned up and transcribed this much at a time, so
+/// a long one is never held in memory whole.
+pub const WINDOW_MS: u64 = 10 * 60 * 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingConfig {
+    /// Chunks transcribed at the same time; 1 sends them one after another.
+    pub max_parallel: u32,
+    /// Audio shared by neighbouring chunks, so a word is never lost to a
+    /// cut that falls inside it.
+    pub overlap_ms: u32,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            max_parallel: 1,
+            overlap_ms: 1000,
+        }
+    }
+}
+
+fn ms_to_samples(ms: u64) -> usize {
+    (ms * SAMPLE_RATE / 1000) as usize
+}
+
+/// Transcribe 16kHz mono audio, splitting it at silence into chunks the
+/// provider accepts if it is longer than [`SttProvider::max_chunk_ms`].
+pub async fn transcribe(
+    provider: &Arc<dyn SttProvider>,
+    audio: &[f32],
+    config: &ProviderConfig,
+    chunking: &ChunkingConfig,
+) -> Result<TranscriptionResult> {
+    let max_len = match provider.max_chunk_ms().map(ms_to_samples) {
+        Some(max_len) if audio.len() > max_len => max_len,
+        _ => return provider.transcribe(audio, config).await,
+    };
+
+    let start = Instant::now();
+    let search = ms_to_samples(SEARCH_MS).min(max_len / 5);
+    let overlap = ms_to_samples(chunking.overlap_ms as u64);
+    let ranges = split_at_silence(audio, max_len, overlap, search);
+    log::info!(
+        "Transcribing {}s of audio as {} chunks with {}",
+        audio.len() as u64 / SAMPLE_RATE,
+        ranges.len(),
+        provider.name()
+    );
+
+    let semaphore = Arc::new(Semaphore::new(chunking.max_parallel.max(1) as usize));
+    let mut results: Vec<Option<TranscriptionResult>> = vec![None; ranges.len()];
+    let mut tasks = JoinSet::new();
+    for (index, range) in ranges.iter().cloned().enumerate() {
+        // Only copy a chunk out once it can be sent
+        let permit = semaphore.clone().acquire_owned().await?;
+        while let Some(joined) = tasks.try_join_next() {
+            let (index, result) = joined??;
+            results[index] = Some(result);
+        }
+
+        let chunk = audio[range].to_vec();
+        let (provider, config) = (provider.clone(), config.clone());
+        tasks.spawn(async move {
+            let _permit = permit;
+            let result = provider
+                .transcribe(&chunk, &config)
+                .await
+                .with_context(|| format!("Chunk {} failed", index + 1))?;
+            anyhow::Ok((index, result))
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined??;
+        results[index] = Some(result);
+    }
+
+    let mut results: Vec<TranscriptionResult> = results.into_iter().flatten().collect();
+    let confidence = weighted_confidence(
+        results
+            .iter()
+            .zip(&ranges)
+            .filter_map(|(result, range)| Some((result.confidence?, range.len() as u64))),
+    );
+    let (segments, words) = merge_timing(&mut results, &ranges);
+    let texts: Vec<&str> = results.iter().map(|r| r.text.as_str()).collect();
+    Ok(TranscriptionResult {
+        text: stitch(&texts, overlap > 0),
+        provider: provider.id(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        language: results.iter().find_map(|r| r.language.clone()),
+        trimmed_ms: 0,
+        segments,
+        words,
+        confidence,
+        // Alternatives of single chunks are not readings of the whole
+        alternatives: Vec::new(),
+    })
+}
+
+pub(crate) fn samples_to_ms(samples: usize) -> u64 {
+    samples as u64 * 1000 / SAMPLE_RATE
+}
+
+/// Reads a recording in windows of at most `max_ms`, each but the last cut
+/// at the quietest point near its end.
+pub struct Windows<'a> {
+    reader: AudioReader<'a>,
+    max_len: usize,
+    /// Audio after the last cut, which starts the next window
+    carry: Vec<f32>,
+}
+
+impl<'a> Windows<'a> {
+    pub fn new(reader: AudioReader<'a>, max_ms: u64) -> Self {
+        Self {
+            reader,
+            max_len: ms_to_samples(max_ms).max(1),
+            carry: Vec::new(),
+        }
+    }
+
+    /// The next window, `None` once the recording is used up.
+    pub fn next_window(&mut self) -> Result<Option<Vec<f32>>> {
+        let mut window = std::mem::take(&mut self.carry);
+        window.append(&mut self.reader.read(self.max_len - window.len())?);
+        if window.is_empty() {
+            return Ok(None);
+        }
+        if !self.reader.is_empty() {
+            let search = ms_to_samples(SEARCH_MS).min(window.len() / 5);
+            let from = window.len() - search;
+            let cut = from + quietest_point(&window[from..], ms_to_samples(FRAME_MS).max(1));
+            self.carry = window.split_off(cut);
+        }
+        Ok(Some(window))
+    }
+}
+
+/// Join the transcripts of consecutive windows of one recording, each with
+/// the number of samples it covers. Times must already be on the
+/// recording's timeline. `None` if there are none.
+pub fn join_windows(windows: Vec<(TranscriptionResult, usize)>) -> Option<TranscriptionResult> {
+    let confidence = weighted_confidence(
+        windows
+            .iter()
+            .filter_map(|(result, len)| Some((result.confidence?, *len as u64))),
+    );
+    let mut results = windows.into_iter().map(|(result, _)| result);
+    let mut joined = results.next()?;
+    for mut result in results {
+        let texts: Vec<&str> = [joined.text.trim(), result.text.trim()]
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .collect();
+        joined.text = texts.join(" ");
+        joined.duration_ms += result.duration_ms;
+        joined.trimmed_ms += result.trimmed_ms;
+        joined.language = joined.language.or(result.language);
+        joined.segments.append(&mut result.segments);
+        joined.words.append(&mut result.words);
+        // Alternatives of one window are not readings of the whole
+        joined.alternatives.clear();
+    }
+    joined.confidence = confidence;
+    Some(joined)
+}
+
+/// Move the chunks' segments and words onto the timeline of the whole
+/// audio. Where neighbours overlap, each keeps what starts on its side of
+/// the cut, so nothing is listed twice.
+fn merge_timing(
+    results: &mut [TranscriptionResult],
+    ranges: &[Range<usize>],
+) -> (Vec<Segment>, Vec<Word>) {
+    let cuts: Vec<u64> = ranges
+        .windows(2)
+        .map(|pair| samples_to_ms((pair[0].end + pair[1].start) / 2))
+        .collect();
+
+    let (mut segments, mut words) = (Vec::new(), Vec::new());
+    for (index, (result, range)) in results.iter_mut().zip(ranges).enumerate() {
+        let offset = samples_to_ms(range.start);
+        result.map_times(|ms| ms + offset);
+        let from = if index == 0 { 0 } else { cuts[index - 1] };
+        let to = cuts.get(index).copied().unwrap_or(u64::MAX);
+        let own = from..to;
+        segments.extend(
+            result
+                .segments
+                .drain(..)
+                .filter(|s| own.contains(&s.start_ms)),
+        );
+        words.extend(result.words.drain(..).filter(|w| own.contains(&w.start_ms)));
+    }
+    (segments, words)
+}
+
+/// Split `audio` into ranges of at most `max_len` samples. Each cut is made
+/// at the quietest frame in the `search` samples before the limit, and
+/// neighbouring ranges share `overlap` samples centred on the cut.
+fn split_at_silence(
+    audio: &[f32],
+    max_len: usize,
+    overlap: usize,
+    search: usize,
+) -> Vec<Range<usize>> {
+    let half = overlap.min(max_len / 4) / 2;
+    let frame_len = ms_to_samples(FRAME_MS).max(1);
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while audio.len() - start > max_len {
+        let limit = start + max_len - half;
+        // The next chunk must start after this one does
+        let from = limit.saturating_sub(search).max(start + 2 * half + 1);
+        let cut = from + quietest_point(&audio[from..limit], frame_len);
+        ranges.push(start..cut + half);
+        start = cut - half;
+    }
+    ranges.push(start..audio.len());
+    ranges
+}
+
+/// Offset of the middle of the quietest frame in `window`, preferring the
+/// latest one so chunks stay long.
+fn quietest_point(window: &[f32], frame_len: usize) -> usize {
+    let mut best = None;
+    for (i, frame) in window.chunks_exact(frame_len).enumerate() {
+        let level = rms(frame);
+        if best.is_none_or(|(_, quietest)| level <= quietest) {
+            best = Some((i, level));
+        }
+    }
+    match best {
+        Some((i, _)) => i * frame_len + frame_len / 2,
+        None => window.len(),
+    }
+}
+
+/// Join chunk transcripts. If the chunks `overlap`, words that appear twice
+/// because of the audio shared across a cut are dropped; otherwise a
+/// repeat is what was said and is kept.
+fn stitch(texts: &[&str], overlap: bool) -> String {
+    let mut words: Vec<&str> = Vec::new();
+    for text in texts {
+        let next: Vec<&str> = text.split_whitespace().collect();
+        let repeated = if overlap {
+            repeated_words(&words, &next)
+        } else {
+            0
+        };
+        words.extend(&next[repeated..]);
+    }
+    words.join(" ")
+}
+
+/// Length of the longest run of words that ends `previous` and starts
+/// `next`, ignoring case and punctuation.
+fn repeated_words(previous: &[&str], next: &[&str]) -> usize {
+    let normalize = |word: &str| -> String {
+        word.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let max = MAX_OVERLAP_WORDS.min(previous.len()).min(next.len());
+    (1..=max)
+        .rev()
+        .find(|&n| {
+            previous[previous.len() - n..]
+                .iter()
+                .zip(&next[..n])
+                .all(|(a, b)| normalize(a) == normalize(b))
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loud audio with 100ms gaps of silence at the given seconds.
+    fn speech_with_gaps(seconds: usize, gaps: &[f64]) -> Vec<f32> {
+        let mut audio: Vec<f32> = (0..seconds * 16000)
+            .map(|i| 0.5 * (i as f32 * 0.05).sin())
+            .collect();
+        for &gap in gaps {
+            let at = (gap * 16000.0) as usize;
+            audio[at..at + 1600].fill(0.0);
+        }
+        audio
+    }
+
+    #[test]
+    fn test_short_audio_is_one_chunk() {
+        let audio = speech_with_gaps(5, &[]);
+        assert_eq!(
+            split_at_silence(&audio, 16000 * 10, 16000, 16000),
+            vec![0..80000]
+        );
+    }
+
+    #[test]
+    fn test_cuts_at_silence_within_limits() {
+        // 25s with pauses at 8s and 17s, chunks of at most 10s
+        let audio = speech_with_gaps(25, &[8.0, 17.0]);
+        let max_len = 16000 * 10;
+        let overlap = 8000;
+        let ranges = split_at_silence(&audio, max_len, overlap, 16000 * 3);
+
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges.last().unwrap().end, audio.len());
+        for range in &ranges {
+            assert!(range.len() <= max_len);
+        }
+        for pair in ranges.windows(2) {
+            // Neighbours share the overlap, centred in the pause
+            assert_eq!(pair[0].end - pair[1].start, overlap);
+            let cut = (pair[0].end + pair[1].start) / 2;
+            assert_eq!(audio[cut], 0.0);
+        }
+    }
+
+    #[test]
+    fn test_cuts_at_the_limit_without_silence() {
+        let audio = speech_with_gaps(30, &[]);
+        let max_len = 16000 * 10;
+        let ranges = split_at_silence(&audio, max_len, 16000, 16000 * 2);
+        assert!(ranges.len() >= 3);
+        assert!(ranges.iter().all(|r| r.len() <= max_len));
+    }
+
+    #[test]
+    fn test_merge_timing_offsets_chunks_and_drops_overlap() {
+        let word = |text: &str, start_ms| Word {
+            text: text.into(),
+            start_ms,
+            end_ms: start_ms + 300,
+            confidence: None,
+        };
+        let result = |words: Vec<Word>| TranscriptionResult {
+            text: String::new(),
+            provider: crate::providers::ProviderId::OpenAiWhisper,
+            duration_ms: 0,
+            language: None,
+            trimmed_ms: 0,
+            segments: Vec::new(),
+            words,
+            confidence: None,
+            alternatives: Vec::new(),
+        };
+        // Chunks of 0-10s and 9-20s, cut at 9.5s
+        let mut results = vec![
+            result(vec![
+                word("one", 1000),
+                word("two", 9200),
+                word("three", 9700),
+            ]),
+            result(vec![
+                word("two", 200),
+                word("three", 700),
+                word("four", 5000),
+            ]),
+        ];
+        let ranges = vec![0..160000, 144000..320000];
+        let (_, words) = merge_timing(&mut results, &ranges);
+        let timed: Vec<(&str, u64)> = words
+            .iter()
+            .map(|w| (w.text.as_str(), w.start_ms))
+            .collect();
+        assert_eq!(
+            timed,
+            vec![
+                ("one", 1000),
+                ("two", 9200),
+                ("three", 9700),
+                ("four", 14000)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stitch_removes_repeated_words() {
+        let texts = [
+            "We met on Tuesday to discuss the",
+            "discuss the budget. It was agreed",
+            "It was agreed that nothing changes.",
+        ];
+        assert_eq!(
+            stitch(&texts, true),
+            "We met on Tuesday to discuss the budget. It was agreed that nothing changes."
+        );
+    }
+
+    #[test]
+    fn test_stitch_ignores_case_and_punctuation() {
+        assert_eq!(
+            stitch(&["and then we left.", "We left early"], true),
+            "and then we left. early"
+        );
+        assert_eq!(stitch(&["no overlap", "at all"], true), "no overlap at all");
+        assert_eq!(stitch(&["", "only the second"], true), "only the second");
+    }
+
+    #[test]
+    fn test_stitch_keeps_repeats_without_overlap() {
+        assert_eq!(
+            stitch(&["I think that", "that is right"], false),
+            "I think that that is right"
+        );
+        assert_eq!(
+            stitch(&["I think that", "that is right"], true),
+            "I think that is right"
+        );
+    }
+
+    #[test]
+    fn test_windows_cut_at_silence() {
+        // 25s with a pause at 8s, windows of at most 10s
+        let audio = speech_with_gaps(25, &[8.0]);
+        let mut windows = Windows::new(AudioReader::from(audio.as_slice()), 10_000);
+        let mut lengths = Vec::new();
+        let mut joined = Vec::new();
+        while let Some(window) = windows.next_window().unwrap() {
+            assert!(window.len() <= 16000 * 10);
+            lengths.push(window.len());
+            joined.extend(window);
+        }
+        assert_eq!(joined, audio);
+        // The first cut falls in the pause
+        assert_eq!(audio[lengths[0]], 0.0);
+        assert_eq!(lengths.len(), 3);
+    }
+}
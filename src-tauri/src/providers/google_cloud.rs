@@ -1,13 +1,45 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use base64::Engine;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use super::{ProviderConfig, ProviderId, SttProvider, TranscriptionResult};
-use crate::audio::encode_wav;
+use super::{
+    secs_to_ms, segments_confidence, weighted_confidence, Alternative, ProviderConfig, ProviderId,
+    Segment, SttProvider, TranscriptionResult, Word,
+};
+use crate::audio::encode::AudioEncoding;
+
+/// `speech:recognize` rejects audio longer than about 60 seconds; longer
+/// audio goes to `speech:longrunningrecognize`.
+const SYNC_LIMIT_MS: u64 = 55 * 1000;
+
+/// Requests are limited to 10 MB, and audio sent inline grows by a third
+/// as base64; 3 minutes of WAV stays below that.
+const MAX_CHUNK_MS: u64 = 3 * 60 * 1000;
+
+const DEFAULT_ENDPOINT: &str = "https://speech.googleapis.com/v1/speech:recognize";
+
+/// How often a long-running operation is checked on.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Give up on a long-running operation after this long.
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Most alternatives `RecognitionConfig.maxAlternatives` allows, the
+/// transcript itself included.
+const MAX_ALTERNATIVES: u32 = 30;
 
 pub struct GoogleCloudProvider;
 
+/// `RecognitionConfig.encoding` for audio uploaded as `encoding`.
+fn google_encoding(encoding: AudioEncoding) -> &'static str {
+    match encoding {
+        AudioEncoding::Wav => "LINEAR16",
+        AudioEncoding::Flac => "FLAC",
+        AudioEncoding::OggOpus => "OGG_OPUS",
+    }
+}
+
 #[derive(serde::Serialize)]
 struct GoogleRequest {
     config: GoogleConfig,
@@ -21,6 +53,9 @@ struct GoogleConfig {
     sample_rate_hertz: u32,
     language_code: String,
     model: String,
+    enable_word_time_offsets: bool,
+    enable_word_confidence: bool,
+    max_alternatives: u32,
 }
 
 #[derive(serde::Serialize)]
@@ -28,9 +63,28 @@ struct GoogleAudio {
     content: String,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(Default, serde::Deserialize)]
 struct GoogleResponse {
-    results: Option<Vec<GoogleResult>>,
+    #[serde(default)]
+    results: Vec<GoogleResult>,
+}
+
+/// A `google.longrunning.Operation`; `response` is set once it is done.
+#[derive(serde::Deserialize)]
+struct GoogleOperation {
+    name: String,
+    #[serde(default)]
+    done: bool,
+    response: Option<GoogleResponse>,
+    error: Option<GoogleStatus>,
+}
+
+#[derive(serde::Deserialize)]
+struct GoogleStatus {
+    #[serde(default)]
+    code: i32,
+    #[serde(default)]
+    message: String,
 }
 
 #[derive(serde::Deserialize)]
@@ -41,6 +95,162 @@ struct GoogleResult {
 #[derive(serde::Deserialize)]
 struct GoogleAlternative {
     transcript: String,
+    confidence: Option<f32>,
+    #[serde(default)]
+    words: Vec<GoogleWord>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleWord {
+    word: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    confidence: Option<f32>,
+}
+
+/// Milliseconds in a protobuf `Duration` as rendered in JSON, e.g. "1.300s".
+fn duration_ms(duration: Option<&str>) -> u64 {
+    duration
+        .and_then(|d| d.strip_suffix('s'))
+        .and_then(|d| d.parse::<f64>().ok())
+        .map_or(0, secs_to_ms)
+}
+
+/// Timing for the transcript of one result, from its words.
+fn timed(alternative: GoogleAlternative) -> (String, Option<Segment>, Vec<Word>) {
+    let words: Vec<Word> = alternative
+        .words
+        .into_iter()
+        .map(|word| Word {
+            text: word.word,
+            start_ms: duration_ms(word.start_time.as_deref()),
+            end_ms: duration_ms(word.end_time.as_deref()),
+            confidence: word.confidence,
+        })
+        .collect();
+    let segment = match (words.first(), words.last()) {
+        (Some(first), Some(last)) => Some(Segment {
+            text: alternative.transcript.trim().to_string(),
+            start_ms: first.start_ms,
+            end_ms: last.end_ms,
+            confidence: alternative.confidence,
+        }),
+        _ => None,
+    };
+    (alternative.transcript, segment, words)
+}
+
+/// Transcript of all results. Google splits longer audio into several,
+/// one after another, each with its own alternatives. The k-th alternative
+/// of the whole takes the k-th reading of each result that has one.
+fn read_results(results: Vec<GoogleResult>, max_alternatives: u32) -> TranscriptionResult {
+    let join = |k: usize| {
+        let texts: Vec<&str> = results
+            .iter()
+            .filter_map(|r| r.alternatives.get(k).or(r.alternatives.first()))
+            .map(|a| a.transcript.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        texts.join(" ")
+    };
+    let alternatives = (1..=max_alternatives as usize)
+        .take_while(|&k| results.iter().any(|r| r.alternatives.len() > k))
+        .map(|k| Alternative {
+            text: join(k),
+            // Confidence of a reading pieced together from parts is unknown
+            confidence: match results.as_slice() {
+                [only] => only.alternatives[k].confidence,
+                _ => None,
+            },
+        })
+        .filter(|alternative| !alternative.text.is_empty())
+        .collect();
+    let text = join(0);
+
+    let (mut segments, mut words) = (Vec::new(), Vec::new());
+    let mut confidences = Vec::new();
+    for result in results {
+        let Some(best) = result.alternatives.into_iter().next() else {
+            continue;
+        };
+        let confidence = best.confidence;
+        let (transcript, segment, mut timed_words) = timed(best);
+        if let Some(confidence) = confidence {
+            confidences.push((confidence, transcript.trim().chars().count() as u64));
+        }
+        segments.extend(segment);
+        words.append(&mut timed_words);
+    }
+
+    TranscriptionResult {
+        text,
+        provider: ProviderId::GoogleCloud,
+        duration_ms: 0,
+        language: None,
+        trimmed_ms: 0,
+        // Weighted by length of text when there is no timing to go on
+        confidence: segments_confidence(&segments).or_else(|| weighted_confidence(confidences)),
+        segments,
+        words,
+        alternatives,
+    }
+}
+
+/// URL of `path` next to the configured `speech:recognize` endpoint, e.g.
+/// `speech:longrunningrecognize` or `operations/{name}`.
+fn sibling_url(endpoint: &str, path: &str) -> String {
+    let base = endpoint.rsplit_once('/').map_or(endpoint, |(base, _)| base);
+    format!("{}/{}", base, path)
+}
+
+/// Send a request, turning an error status into an error.
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Google Cloud API error ({}): {}", status, body));
+    }
+    Ok(response)
+}
+
+/// Start a `speech:longrunningrecognize` operation and poll it until it
+/// is done.
+async fn recognize_long(
+    client: &reqwest::Client,
+    endpoint: &str,
+    api_key: &str,
+    request: &GoogleRequest,
+) -> Result<GoogleResponse> {
+    let url = sibling_url(endpoint, "speech:longrunningrecognize");
+    let url = format!("{}?key={}", url, api_key);
+    let mut operation: GoogleOperation =
+        send(client.post(&url).json(request)).await?.json().await?;
+
+    let started = Instant::now();
+    while !operation.done {
+        if started.elapsed() > OPERATION_TIMEOUT {
+            return Err(anyhow!(
+                "Google Cloud operation {} timed out",
+                operation.name
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let url = sibling_url(endpoint, &format!("operations/{}", operation.name));
+        let url = format!("{}?key={}", url, api_key);
+        operation = send(client.get(&url)).await?.json().await?;
+    }
+
+    if let Some(error) = operation.error {
+        return Err(anyhow!(
+            "Google Cloud API error ({}): {}",
+            error.code,
+            error.message
+        ));
+    }
+    // A done operation without results heard no speech
+    Ok(operation.response.unwrap_or_default())
 }
 
 #[async_trait]
@@ -57,6 +267,10 @@ impl SttProvider for GoogleCloudProvider {
         true
     }
 
+    fn max_chunk_ms(&self) -> Option<u64> {
+        Some(MAX_CHUNK_MS)
+    }
+
     async fn transcribe(
         &self,
         audio_data: &[f32],
@@ -69,9 +283,8 @@ impl SttProvider for GoogleCloudProvider {
 
         let start = Instant::now();
 
-        // Encode audio as WAV
-        let wav_bytes = encode_wav(audio_data, 16000);
-        let audio_content = base64::engine::general_purpose::STANDARD.encode(&wav_bytes);
+        let audio_bytes = config.encoding.encode(audio_data, 16000)?;
+        let audio_content = base64::engine::general_purpose::STANDARD.encode(&audio_bytes);
 
         let language_code = config
             .language
@@ -81,54 +294,129 @@ impl SttProvider for GoogleCloudProvider {
 
         let request = GoogleRequest {
             config: GoogleConfig {
-                encoding: "LINEAR16".into(),
+                encoding: google_encoding(config.encoding).into(),
                 sample_rate_hertz: 16000,
                 language_code: language_code.clone(),
                 model: config.model.as_deref().unwrap_or("default").into(),
+                enable_word_time_offsets: true,
+                enable_word_confidence: true,
+                max_alternatives: config
+                    .max_alternatives
+                    .saturating_add(1)
+                    .min(MAX_ALTERNATIVES),
             },
             audio: GoogleAudio {
                 content: audio_content,
             },
         };
 
-        let endpoint = config.endpoint.as_deref().unwrap_or(
-            "https://speech.googleapis.com/v1/speech:recognize",
+        let endpoint = config.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT);
+
+        let client = reqwest::Client::new();
+        let audio_ms = audio_data.len() as u64 * 1000 / 16000;
+        let response = if audio_ms > SYNC_LIMIT_MS {
+            recognize_long(&client, endpoint, api_key, &request).await?
+        } else {
+            let url = format!("{}?key={}", endpoint, api_key);
+            send(client.post(&url).json(&request)).await?.json().await?
+        };
+
+        let mut result = read_results(response.results, config.max_alternatives);
+        result.duration_ms = start.elapsed().as_millis() as u64;
+        result.language = Some(language_code);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_time_offsets() {
+        let alternative: GoogleAlternative = serde_json::from_str(
+            r#"{
+                "transcript": "hello world",
+                "confidence": 0.92,
+                "words": [
+                    {"word": "hello", "startTime": "0.300s", "endTime": "0.800s", "confidence": 0.95},
+                    {"word": "world", "startTime": "0.800s", "endTime": "1.250s", "confidence": 0.89}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let (text, segment, words) = timed(alternative);
+        assert_eq!(text, "hello world");
+        assert_eq!(words.len(), 2);
+        assert_eq!((words[1].start_ms, words[1].end_ms), (800, 1250));
+        assert_eq!(words[0].confidence, Some(0.95));
+        let segment = segment.unwrap();
+        assert_eq!((segment.start_ms, segment.end_ms), (300, 1250));
+        assert_eq!(segment.confidence, Some(0.92));
+    }
+
+    #[test]
+    fn test_joins_all_results() {
+        let response: GoogleResponse = serde_json::from_str(
+            r#"{"results": [
+                {"alternatives": [
+                    {"transcript": "recognize speech", "confidence": 0.71, "words": [
+                        {"word": "recognize", "startTime": "0s", "endTime": "0.600s"},
+                        {"word": "speech", "startTime": "0.600s", "endTime": "1s"}
+                    ]},
+                    {"transcript": "wreck a nice beach", "confidence": 0.22}
+                ]},
+                {"alternatives": [
+                    {"transcript": " today", "confidence": 0.91, "words": [
+                        {"word": "today", "startTime": "2s", "endTime": "3s"}
+                    ]}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        let result = read_results(response.results, 3);
+        assert_eq!(result.text, "recognize speech today");
+        assert_eq!(result.segments.len(), 2);
+        assert_eq!(result.words.len(), 3);
+        assert_eq!(result.words[2].start_ms, 2000);
+        // 1s at 0.71 and 1s at 0.91
+        assert!((result.confidence.unwrap() - 0.81).abs() < 1e-6);
+        assert_eq!(
+            result.alternatives,
+            vec![Alternative {
+                text: "wreck a nice beach today".into(),
+                confidence: None,
+            }]
         );
+    }
 
-        let url = format!("{}?key={}", endpoint, api_key);
+    #[test]
+    fn test_operation() {
+        let operation: GoogleOperation =
+            serde_json::from_str(r#"{"name": "123", "metadata": {"progressPercent": 40}}"#)
+                .unwrap();
+        assert!(!operation.done);
+        assert!(operation.response.is_none());
 
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&url)
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!(
-                "Google Cloud API error ({}): {}",
-                status,
-                body
-            ));
-        }
+        let operation: GoogleOperation = serde_json::from_str(
+            r#"{"name": "123", "done": true, "response": {
+                "@type": "type.googleapis.com/google.cloud.speech.v1.LongRunningRecognizeResponse",
+                "results": [{"alternatives": [{"transcript": "done"}]}]
+            }}"#,
+        )
+        .unwrap();
+        assert!(operation.done);
+        assert_eq!(
+            read_results(operation.response.unwrap().results, 0).text,
+            "done"
+        );
+    }
 
-        let result: GoogleResponse = response.json().await?;
-        let duration_ms = start.elapsed().as_millis() as u64;
-
-        let text = result
-            .results
-            .and_then(|r| r.into_iter().next())
-            .and_then(|r| r.alternatives.into_iter().next())
-            .map(|a| a.transcript)
-            .unwrap_or_default();
-
-        Ok(TranscriptionResult {
-            text,
-            provider: ProviderId::GoogleCloud,
-            duration_ms,
-            language: Some(language_code),
-        })
+    #[test]
+    fn test_sibling_url() {
+        assert_eq!(
+            sibling_url(DEFAULT_ENDPOINT, "operations/123"),
+            "https://speech.googleapis.com/v1/operations/123"
+        );
     }
 }
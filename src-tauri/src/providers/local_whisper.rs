@@ -2,6 +2,8 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::path::PathBuf;
 
+#[cfg(feature = "local-whisper")]
+use super::{segments_confidence, Segment, Word};
 use super::{ProviderConfig, ProviderId, SttProvider, TranscriptionResult};
 
 pub struct LocalWhisperProvider {
@@ -56,6 +58,73 @@ impl SttProvider for LocalWhisperProvider {
     }
 }
 
+/// Text, segments and words of a local transcription.
+#[cfg(feature = "local-whisper")]
+type Transcript = (String, Vec<Segment>, Vec<Word>);
+
+/// whisper.cpp token handling. Kept out of the feature so it is tested
+/// in every build.
+#[cfg(any(test, feature = "local-whisper"))]
+mod tokens {
+    use crate::providers::Word;
+
+    /// A whisper.cpp token with its timing in centiseconds and probability.
+    pub struct Token {
+        pub text: String,
+        pub t0: i64,
+        pub t1: i64,
+        pub p: f32,
+    }
+
+    pub fn centis_to_ms(centis: i64) -> u64 {
+        centis.max(0) as u64 * 10
+    }
+
+    pub fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+        let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// Join whisper.cpp tokens into words. A token starting with a space
+    /// begins a new word; special tokens such as `[_BEG_]` are skipped. A
+    /// word's confidence is the mean probability of its tokens.
+    pub fn words_from_tokens(tokens: &[Token]) -> Vec<Word> {
+        let mut words: Vec<(Word, Vec<f32>)> = Vec::new();
+        for token in tokens {
+            if token.text.starts_with("[_") || token.text.starts_with("<|") {
+                continue;
+            }
+            match words.last_mut() {
+                Some((word, probabilities)) if !token.text.starts_with(' ') => {
+                    word.text.push_str(&token.text);
+                    word.end_ms = centis_to_ms(token.t1);
+                    probabilities.push(token.p);
+                }
+                _ => words.push((
+                    Word {
+                        text: token.text.trim_start().to_string(),
+                        start_ms: centis_to_ms(token.t0),
+                        end_ms: centis_to_ms(token.t1),
+                        confidence: None,
+                    },
+                    vec![token.p],
+                )),
+            }
+        }
+        words
+            .into_iter()
+            .filter(|(word, _)| !word.text.is_empty())
+            .map(|(mut word, probabilities)| {
+                word.confidence = mean(probabilities.into_iter());
+                word
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "local-whisper")]
+use tokens::{centis_to_ms, mean, words_from_tokens, Token};
+
 #[cfg(feature = "local-whisper")]
 async fn transcribe_local(
     audio_data: &[f32],
@@ -76,7 +145,7 @@ async fn transcribe_local(
     let language = config.language.clone();
     let start = Instant::now();
 
-    let text = tokio::task::spawn_blocking(move || -> Result<String> {
+    let (text, segments, words) = tokio::task::spawn_blocking(move || -> Result<Transcript> {
         let ctx = whisper_rs::WhisperContext::new_with_params(
             model_path.to_str().unwrap(),
             whisper_rs::WhisperContextParameters::default(),
@@ -98,12 +167,15 @@ async fn transcribe_local(
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
+        params.set_token_timestamps(true);
 
         state
             .full(params, &audio)
             .map_err(|e| anyhow!("Whisper transcription failed: {}", e))?;
 
         let mut text = String::new();
+        let mut segments = Vec::new();
+        let mut words = Vec::new();
         let num_segments = state
             .full_n_segments()
             .map_err(|e| anyhow!("Failed to get segments: {}", e))?;
@@ -113,9 +185,43 @@ async fn transcribe_local(
                 .full_get_segment_text(i)
                 .map_err(|e| anyhow!("Failed to get segment text: {}", e))?;
             text.push_str(&segment);
+
+            let mut tokens = Vec::new();
+            let num_tokens = state
+                .full_n_tokens(i)
+                .map_err(|e| anyhow!("Failed to get tokens: {}", e))?;
+            for j in 0..num_tokens {
+                let token = state
+                    .full_get_token_text(i, j)
+                    .map_err(|e| anyhow!("Failed to get token text: {}", e))?;
+                let data = state
+                    .full_get_token_data(i, j)
+                    .map_err(|e| anyhow!("Failed to get token data: {}", e))?;
+                tokens.push(Token {
+                    text: token,
+                    t0: data.t0,
+                    t1: data.t1,
+                    p: data.p,
+                });
+            }
+            let segment_words = words_from_tokens(&tokens);
+
+            let t0 = state
+                .full_get_segment_t0(i)
+                .map_err(|e| anyhow!("Failed to get segment start: {}", e))?;
+            let t1 = state
+                .full_get_segment_t1(i)
+                .map_err(|e| anyhow!("Failed to get segment end: {}", e))?;
+            segments.push(Segment {
+                text: segment.trim().to_string(),
+                start_ms: centis_to_ms(t0),
+                end_ms: centis_to_ms(t1),
+                confidence: mean(segment_words.iter().filter_map(|w| w.confidence)),
+            });
+            words.extend(segment_words);
         }
 
-        Ok(text.trim().to_string())
+        Ok((text.trim().to_string(), segments, words))
     })
     .await??;
 
@@ -126,5 +232,42 @@ async fn transcribe_local(
         provider: ProviderId::LocalWhisper,
         duration_ms,
         language: config.language.clone(),
+        trimmed_ms: 0,
+        confidence: segments_confidence(&segments),
+        segments,
+        words,
+        alternatives: Vec::new(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::tokens::{words_from_tokens, Token};
+
+    fn token(text: &str, t0: i64, t1: i64, p: f32) -> Token {
+        Token {
+            text: text.into(),
+            t0,
+            t1,
+            p,
+        }
+    }
+
+    #[test]
+    fn test_words_from_tokens() {
+        let tokens = [
+            token("[_BEG_]", 0, 0, 1.0),
+            token(" Hel", 10, 30, 0.8),
+            token("lo", 30, 45, 0.6),
+            token(",", 45, 46, 0.9),
+            token(" world", 60, 95, 0.5),
+            token("[_TT_50]", 95, 95, 1.0),
+        ];
+        let words = words_from_tokens(&tokens);
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello,", "world"]);
+        assert_eq!((words[0].start_ms, words[0].end_ms), (100, 460));
+        assert!((words[0].confidence.unwrap() - 0.7667).abs() < 0.001);
+        assert_eq!((words[1].start_ms, words[1].end_ms), (600, 950));
+    }
+}
@@ -1,13 +1,18 @@
+pub mod chunking;
 pub mod google_cloud;
 pub mod local_whisper;
 pub mod native_stt;
+pub mod openai_realtime;
 pub mod openai_whisper;
+pub mod streaming;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+use crate::audio::encode::AudioEncoding;
 use crate::settings::AppSettings;
+use streaming::{AudioChunks, HypothesisSender, StreamingConfig};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum ProviderId {
@@ -15,6 +20,7 @@ pub enum ProviderId {
     GoogleCloud,
     LocalWhisper,
     NativeStt,
+    OpenAiRealtime,
 }
 
 impl std::fmt::Display for ProviderId {
@@ -24,6 +30,7 @@ impl std::fmt::Display for ProviderId {
             ProviderId::GoogleCloud => write!(f, "Google Cloud"),
             ProviderId::LocalWhisper => write!(f, "Local Whisper"),
             ProviderId::NativeStt => write!(f, "Native STT"),
+            ProviderId::OpenAiRealtime => write!(f, "OpenAI Realtime"),
         }
     }
 }
@@ -34,6 +41,17 @@ pub struct ProviderConfig {
     pub model: Option<String>,
     pub language: Option<String>,
     pub endpoint: Option<String>,
+    /// Format audio is uploaded in, for providers that upload it.
+    #[serde(default)]
+    pub encoding: AudioEncoding,
+    /// Other readings to return besides the transcript, for providers
+    /// that offer them.
+    #[serde(default = "default_max_alternatives")]
+    pub max_alternatives: u32,
+}
+
+fn default_max_alternatives() -> u32 {
+    3
 }
 
 impl Default for ProviderConfig {
@@ -43,6 +61,8 @@ impl Default for ProviderConfig {
             model: None,
             language: Some("auto".into()),
             endpoint: None,
+            encoding: AudioEncoding::default(),
+            max_alternatives: default_max_alternatives(),
         }
     }
 }
@@ -53,6 +73,121 @@ pub struct TranscriptionResult {
     pub provider: ProviderId,
     pub duration_ms: u64,
     pub language: Option<String>,
+    /// Silence removed before upload, in milliseconds.
+    #[serde(default)]
+    pub trimmed_ms: u64,
+    /// Phrases with their timing, if the provider reports it.
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    /// Words with their timing, if the provider reports it.
+    #[serde(default)]
+    pub words: Vec<Word>,
+    /// How sure the provider is of the transcript, 0.0 to 1.0.
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// Other readings of the whole audio, most likely first.
+    #[serde(default)]
+    pub alternatives: Vec<Alternative>,
+}
+
+impl TranscriptionResult {
+    /// Move every segment and word time through `f`, e.g. to make times
+    /// relative to a longer recording the audio was cut from.
+    pub fn map_times(&mut self, f: impl Fn(u64) -> u64) {
+        for segment in &mut self.segments {
+            segment.start_ms = f(segment.start_ms);
+            segment.end_ms = f(segment.end_ms);
+        }
+        for word in &mut self.words {
+            word.start_ms = f(word.start_ms);
+            word.end_ms = f(word.end_ms);
+        }
+    }
+}
+
+/// Another reading of the audio than the transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alternative {
+    pub text: String,
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+/// When a transcription is too uncertain to paste without asking.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfidenceConfig {
+    /// Hold back auto-paste and ask first when confidence is low.
+    pub confirm_low: bool,
+    /// Confidence, 0.0 to 1.0, below which to ask.
+    pub threshold: f32,
+}
+
+impl Default for ConfidenceConfig {
+    fn default() -> Self {
+        Self {
+            confirm_low: false,
+            threshold: 0.6,
+        }
+    }
+}
+
+impl ConfidenceConfig {
+    /// Whether `result` should be confirmed before it is pasted. Results
+    /// from providers that report no confidence never are.
+    pub fn needs_confirmation(&self, result: &TranscriptionResult) -> bool {
+        self.confirm_low && result.confidence.is_some_and(|c| c < self.threshold)
+    }
+}
+
+/// Mean of confidences weighted by how much audio each covers; `None` if
+/// there are none.
+pub(crate) fn weighted_confidence(parts: impl IntoIterator<Item = (f32, u64)>) -> Option<f32> {
+    let (sum, total) = parts
+        .into_iter()
+        .fold((0.0, 0), |(sum, total), (confidence, weight)| {
+            (sum + confidence as f64 * weight as f64, total + weight)
+        });
+    (total > 0).then(|| (sum / total as f64) as f32)
+}
+
+/// Confidence of a transcript from its segments, weighted by their length.
+pub(crate) fn segments_confidence(segments: &[Segment]) -> Option<f32> {
+    weighted_confidence(segments.iter().filter_map(|segment| {
+        Some((
+            segment.confidence?,
+            segment.end_ms.saturating_sub(segment.start_ms),
+        ))
+    }))
+}
+
+/// A phrase or sentence as the provider split the transcript. Times are
+/// milliseconds from the start of the recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// 0.0 to 1.0, if the provider reports it.
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+/// A recognised word. Times are milliseconds from the start of the
+/// recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// 0.0 to 1.0, if the provider reports it.
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+/// Seconds as reported by providers to milliseconds.
+pub(crate) fn secs_to_ms(secs: f64) -> u64 {
+    (secs.max(0.0) * 1000.0).round() as u64
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,11 +202,29 @@ pub trait SttProvider: Send + Sync {
     fn id(&self) -> ProviderId;
     fn name(&self) -> &str;
     fn is_available(&self) -> bool;
+    /// Longest audio accepted in one request. Longer recordings are split
+    /// by [`chunking::transcribe`].
+    fn max_chunk_ms(&self) -> Option<u64> {
+        None
+    }
     async fn transcribe(
         &self,
         audio_data: &[f32],
         config: &ProviderConfig,
     ) -> Result<TranscriptionResult>;
+    /// Transcribe audio while it is still being recorded, sending
+    /// hypotheses as words are recognised. Returns the whole transcript
+    /// once `audio` ends. Providers without a streaming API are fed the
+    /// audio between pauses by [`streaming::transcribe_by_pauses`].
+    async fn transcribe_stream(
+        &self,
+        audio: AudioChunks,
+        hypotheses: HypothesisSender,
+        config: &ProviderConfig,
+        streaming: &StreamingConfig,
+    ) -> Result<TranscriptionResult> {
+        streaming::transcribe_by_pauses(self, audio, hypotheses, config, streaming).await
+    }
 }
 
 pub struct ProviderManager {
@@ -88,6 +241,7 @@ impl ProviderManager {
                 settings.local_whisper_model_path.as_deref(),
             )),
             std::sync::Arc::new(native_stt::NativeSttProvider),
+            std::sync::Arc::new(openai_realtime::OpenAiRealtimeProvider),
         ];
 
         Self {
@@ -125,3 +279,44 @@ impl ProviderManager {
         &self.active_provider
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(confidence: Option<f32>) -> TranscriptionResult {
+        TranscriptionResult {
+            text: "hello".into(),
+            provider: ProviderId::GoogleCloud,
+            duration_ms: 0,
+            language: None,
+            trimmed_ms: 0,
+            segments: Vec::new(),
+            words: Vec::new(),
+            confidence,
+            alternatives: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_weighted_confidence() {
+        let confidence = weighted_confidence([(0.9, 3000), (0.5, 1000)]).unwrap();
+        assert!((confidence - 0.8).abs() < 1e-6);
+        assert_eq!(weighted_confidence([(0.9, 0)]), None);
+        assert_eq!(weighted_confidence([]), None);
+    }
+
+    #[test]
+    fn test_needs_confirmation() {
+        let config = ConfidenceConfig {
+            confirm_low: true,
+            threshold: 0.6,
+        };
+        assert!(config.needs_confirmation(&result(Some(0.4))));
+        assert!(!config.needs_confirmation(&result(Some(0.8))));
+        // Nothing to go on
+        assert!(!config.needs_confirmation(&result(None)));
+        let off = ConfidenceConfig::default();
+        assert!(!off.needs_confirmation(&result(Some(0.1))));
+    }
+}
@@ -43,6 +43,11 @@ impl SttProvider for NativeSttProvider {
             provider: ProviderId::NativeStt,
             duration_ms,
             language: config.language.clone(),
+            trimmed_ms: 0,
+            segments: Vec::new(),
+            words: Vec::new(),
+            confidence: None,
+            alternatives: Vec::new(),
         })
     }
 }
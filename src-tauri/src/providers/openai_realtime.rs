@@ -0,0 +1,590 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::streaming::{AudioChunks, Hypothesis, HypothesisSender, StreamingConfig};
+use super::{ProviderConfig, ProviderId, Segment, SttProvider, TranscriptionResult};
+
+const DEFAULT_ENDPOINT: &str = "wss://api.openai.com/v1/realtime?intent=transcription";
+const DEFAULT_MODEL: &str = "gpt-4o-transcribe";
+
+/// Realtime sessions take 16-bit PCM at 24kHz, so the 16kHz recording is
+/// upsampled on the way out.
+const SESSION_RATE: u32 = 24000;
+
+/// How long to wait for the last transcripts once the audio has ended.
+const FINISH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Audio sent per message when transcribing a complete recording.
+const BATCH_CHUNK_SAMPLES: usize = 16000 / 10;
+
+/// Transcribes over a WebSocket session as the audio is recorded. The
+/// server splits the audio at pauses and transcribes each part as soon as
+/// it ends, so little is left to do on release.
+pub struct OpenAiRealtimeProvider;
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+    code: Option<String>,
+}
+
+/// The server events used; everything else is ignored.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum ServerEvent {
+    #[serde(rename = "input_audio_buffer.speech_started")]
+    SpeechStarted {
+        item_id: String,
+        audio_start_ms: u64,
+    },
+    #[serde(rename = "input_audio_buffer.speech_stopped")]
+    SpeechStopped { item_id: String, audio_end_ms: u64 },
+    #[serde(rename = "input_audio_buffer.committed")]
+    Committed { item_id: String },
+    #[serde(rename = "conversation.item.input_audio_transcription.delta")]
+    Delta { item_id: String, delta: String },
+    #[serde(rename = "conversation.item.input_audio_transcription.completed")]
+    Completed { item_id: String, transcript: String },
+    #[serde(rename = "conversation.item.input_audio_transcription.failed")]
+    Failed { error: ApiError },
+    #[serde(rename = "error")]
+    Error { error: ApiError },
+    #[serde(other)]
+    Other,
+}
+
+/// One part of the audio, as split by the server.
+struct Item {
+    id: String,
+    text: String,
+    done: bool,
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+}
+
+/// Puts transcripts back in audio order. Parts are transcribed in
+/// parallel, so a later one can complete first.
+#[derive(Default)]
+struct Transcript {
+    pending: VecDeque<Item>,
+    finals: Vec<String>,
+    segments: Vec<Segment>,
+}
+
+impl Transcript {
+    fn item(&mut self, id: &str) -> &mut Item {
+        let index = match self.pending.iter().position(|item| item.id == id) {
+            Some(index) => index,
+            None => {
+                self.pending.push_back(Item {
+                    id: id.to_string(),
+                    text: String::new(),
+                    done: false,
+                    start_ms: None,
+                    end_ms: None,
+                });
+                self.pending.len() - 1
+            }
+        };
+        &mut self.pending[index]
+    }
+
+    fn speech_started(&mut self, id: &str, ms: u64) {
+        self.item(id).start_ms = Some(ms);
+    }
+
+    fn speech_stopped(&mut self, id: &str, ms: u64) {
+        self.item(id).end_ms = Some(ms);
+    }
+
+    fn committed(&mut self, id: &str) {
+        self.item(id);
+    }
+
+    fn delta(&mut self, id: &str, delta: &str) {
+        self.item(id).text.push_str(delta);
+    }
+
+    fn completed(&mut self, id: &str, transcript: &str) {
+        let item = self.item(id);
+        item.text = transcript.to_string();
+        item.done = true;
+    }
+
+    /// Hypotheses for the text so far: a final one for each part that is
+    /// complete and in order, then an interim one for the rest.
+    fn settle(&mut self) -> Vec<Hypothesis> {
+        let mut hypotheses = Vec::new();
+        while self.pending.front().is_some_and(|item| item.done) {
+            let item = self.pending.pop_front().unwrap();
+            let text = item.text.trim().to_string();
+            if let (Some(start_ms), Some(end_ms)) = (item.start_ms, item.end_ms) {
+                self.segments.push(Segment {
+                    text: text.clone(),
+                    start_ms,
+                    end_ms,
+                    confidence: None,
+                });
+            }
+            hypotheses.push(Hypothesis {
+                text: text.clone(),
+                is_final: true,
+            });
+            self.finals.push(text);
+        }
+        let interim = join(self.pending.iter().map(|item| item.text.as_str()));
+        if !interim.is_empty() {
+            hypotheses.push(Hypothesis {
+                text: interim,
+                is_final: false,
+            });
+        }
+        hypotheses
+    }
+
+    fn is_settled(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn text(&self) -> String {
+        join(self.finals.iter().map(String::as_str))
+    }
+}
+
+fn join<'a>(texts: impl Iterator<Item = &'a str>) -> String {
+    texts
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Linear 16kHz to 24kHz conversion that carries its position across
+/// chunks. Speech has little above 8kHz, so this is enough.
+struct Upsampler {
+    previous: f32,
+    position: f64,
+}
+
+impl Upsampler {
+    fn new() -> Self {
+        Self {
+            previous: 0.0,
+            position: 0.0,
+        }
+    }
+
+    fn push(&mut self, input: &[f32], out: &mut Vec<u8>) {
+        let step = 16000.0 / SESSION_RATE as f64;
+        for &sample in input {
+            while self.position < 1.0 {
+                let value = self.previous + (sample - self.previous) * self.position as f32;
+                let value = (value.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                out.extend_from_slice(&value.to_le_bytes());
+                self.position += step;
+            }
+            self.position -= 1.0;
+            self.previous = sample;
+        }
+    }
+}
+
+/// The configured endpoint, or the default if the field was left blank.
+fn endpoint(config: &ProviderConfig) -> &str {
+    config
+        .endpoint
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .unwrap_or(DEFAULT_ENDPOINT)
+}
+
+fn session_update(config: &ProviderConfig) -> Message {
+    let language = config.language.as_deref().filter(|l| *l != "auto");
+    let model = config.model.as_deref().unwrap_or(DEFAULT_MODEL);
+    let event = json!({
+        "type": "transcription_session.update",
+        "session": {
+            "input_audio_format": "pcm16",
+            "input_audio_transcription": {
+                "model": model,
+                "language": language,
+            },
+            "turn_detection": {
+                "type": "server_vad",
+                "silence_duration_ms": 500,
+            },
+        },
+    });
+    Message::Text(event.to_string())
+}
+
+fn append(audio: &[f32], upsampler: &mut Upsampler) -> Message {
+    let mut pcm = Vec::with_capacity(audio.len() * 3);
+    upsampler.push(audio, &mut pcm);
+    let event = json!({
+        "type": "input_audio_buffer.append",
+        "audio": base64::engine::general_purpose::STANDARD.encode(pcm),
+    });
+    Message::Text(event.to_string())
+}
+
+fn commit() -> Message {
+    Message::Text(json!({ "type": "input_audio_buffer.commit" }).to_string())
+}
+
+#[async_trait]
+impl SttProvider for OpenAiRealtimeProvider {
+    fn id(&self) -> ProviderId {
+        ProviderId::OpenAiRealtime
+    }
+
+    fn name(&self) -> &str {
+        "OpenAI Realtime"
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn transcribe(
+        &self,
+        audio_data: &[f32],
+        config: &ProviderConfig,
+    ) -> Result<TranscriptionResult> {
+        let (audio_tx, audio_rx) = mpsc::unbounded_channel();
+        for chunk in audio_data.chunks(BATCH_CHUNK_SAMPLES) {
+            let _ = audio_tx.send(chunk.to_vec());
+        }
+        drop(audio_tx);
+        let (hypotheses, _) = mpsc::unbounded_channel();
+        self.transcribe_stream(audio_rx, hypotheses, config, &StreamingConfig::default())
+            .await
+    }
+
+    async fn transcribe_stream(
+        &self,
+        mut audio: AudioChunks,
+        hypotheses: HypothesisSender,
+        config: &ProviderConfig,
+        _streaming: &StreamingConfig,
+    ) -> Result<TranscriptionResult> {
+        let api_key = config
+            .api_key
+            .as_ref()
+            .ok_or_else(|| anyhow!("OpenAI API key not configured"))?;
+        let endpoint = endpoint(config);
+
+        let mut request = endpoint
+            .into_client_request()
+            .with_context(|| format!("Invalid realtime endpoint {}", endpoint))?;
+        let headers = request.headers_mut();
+        headers.insert("Authorization", format!("Bearer {}", api_key).parse()?);
+        headers.insert("OpenAI-Beta", HeaderValue::from_static("realtime=v1"));
+
+        let start = Instant::now();
+        let (socket, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .context("Cannot connect to the realtime endpoint")?;
+        let (mut sink, mut events) = socket.split();
+        sink.send(session_update(config)).await?;
+
+        let mut upsampler = Upsampler::new();
+        let mut transcript = Transcript::default();
+        // When to give up on the last transcripts, set once the audio ends
+        let mut finish_by: Option<tokio::time::Instant> = None;
+        // Whether the server has answered the commit of the last audio.
+        // Every pause the server detects is committed after its
+        // `speech_stopped`, so a commit beyond those answers ours.
+        let mut flushed = false;
+        let (mut pauses, mut commits) = (0, 0);
+
+        loop {
+            let deadline =
+                finish_by.unwrap_or_else(|| tokio::time::Instant::now() + FINISH_TIMEOUT);
+            let message = tokio::select! {
+                chunk = audio.recv(), if finish_by.is_none() => {
+                    match chunk {
+                        Some(chunk) => sink.send(append(&chunk, &mut upsampler)).await?,
+                        None => {
+                            sink.send(commit()).await?;
+                            finish_by = Some(tokio::time::Instant::now() + FINISH_TIMEOUT);
+                        }
+                    }
+                    continue;
+                }
+                _ = tokio::time::sleep_until(deadline), if finish_by.is_some() => {
+                    return Err(anyhow!("Timed out waiting for the realtime transcript"));
+                }
+                message = events.next() => message,
+            };
+
+            let text = match message {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | None => {
+                    if finish_by.is_some() && transcript.is_settled() {
+                        break;
+                    }
+                    return Err(anyhow!(
+                        "Realtime session closed before the transcript was complete"
+                    ));
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+            };
+
+            match serde_json::from_str::<ServerEvent>(&text)? {
+                ServerEvent::SpeechStarted {
+                    item_id,
+                    audio_start_ms,
+                } => transcript.speech_started(&item_id, audio_start_ms),
+                ServerEvent::SpeechStopped {
+                    item_id,
+                    audio_end_ms,
+                } => {
+                    pauses += 1;
+                    transcript.speech_stopped(&item_id, audio_end_ms);
+                }
+                ServerEvent::Committed { item_id } => {
+                    commits += 1;
+                    transcript.committed(&item_id);
+                    flushed |= finish_by.is_some() && commits > pauses;
+                }
+                ServerEvent::Delta { item_id, delta } => transcript.delta(&item_id, &delta),
+                ServerEvent::Completed {
+                    item_id,
+                    transcript: text,
+                } => transcript.completed(&item_id, &text),
+                ServerEvent::Failed { error } => {
+                    return Err(anyhow!("Realtime transcription failed: {}", error.message));
+                }
+                // Nothing was left to commit after the last pause
+                ServerEvent::Error { error }
+                    if finish_by.is_some()
+                        && error.code.as_deref() == Some("input_audio_buffer_commit_empty") =>
+                {
+                    flushed = true;
+                }
+                ServerEvent::Error { error } => {
+                    return Err(anyhow!("OpenAI realtime error: {}", error.message));
+                }
+                ServerEvent::Other => continue,
+            }
+
+            for hypothesis in transcript.settle() {
+                let _ = hypotheses.send(hypothesis);
+            }
+            if flushed && transcript.is_settled() {
+                break;
+            }
+        }
+        let _ = sink.send(Message::Close(None)).await;
+
+        Ok(TranscriptionResult {
+            text: transcript.text(),
+            provider: ProviderId::OpenAiRealtime,
+            duration_ms: start.elapsed().as_millis() as u64,
+            language: config.language.clone(),
+            trimmed_ms: 0,
+            segments: std::mem::take(&mut transcript.segments),
+            words: Vec::new(),
+            confidence: None,
+            alternatives: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_transcript_keeps_audio_order() {
+        let mut transcript = Transcript::default();
+        transcript.speech_started("a", 300);
+        transcript.speech_stopped("a", 1200);
+        transcript.committed("a");
+        transcript.delta("a", "Hel");
+        assert_eq!(
+            transcript.settle(),
+            vec![Hypothesis {
+                text: "Hel".into(),
+                is_final: false
+            }]
+        );
+
+        // The second part completes before the first
+        transcript.committed("b");
+        transcript.completed("b", " world.");
+        assert_eq!(transcript.settle()[0].text, "Hel world.");
+        transcript.completed("a", "Hello");
+        let settled = transcript.settle();
+        assert_eq!(settled.len(), 2);
+        assert!(settled.iter().all(|h| h.is_final));
+        assert!(transcript.is_settled());
+        assert_eq!(transcript.text(), "Hello world.");
+        // Only the first part was timed
+        assert_eq!(transcript.segments.len(), 1);
+        assert_eq!(
+            (
+                transcript.segments[0].start_ms,
+                transcript.segments[0].end_ms
+            ),
+            (300, 1200)
+        );
+    }
+
+    #[test]
+    fn test_upsampler_keeps_rate_across_chunks() {
+        let mut upsampler = Upsampler::new();
+        let mut pcm = Vec::new();
+        let audio = vec![0.5; 16000];
+        for chunk in audio.chunks(333) {
+            upsampler.push(chunk, &mut pcm);
+        }
+        let samples: Vec<i16> = pcm
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert!((samples.len() as i64 - SESSION_RATE as i64).abs() <= 1);
+        assert!(samples[10..]
+            .iter()
+            .all(|&s| s == (0.5 * i16::MAX as f32) as i16));
+    }
+
+    #[test]
+    fn test_blank_endpoint_uses_default() {
+        let mut config = ProviderConfig::default();
+        assert_eq!(endpoint(&config), DEFAULT_ENDPOINT);
+        config.endpoint = Some("  ".into());
+        assert_eq!(endpoint(&config), DEFAULT_ENDPOINT);
+        config.endpoint = Some(" ws://localhost:8080 ".into());
+        assert_eq!(endpoint(&config), "ws://localhost:8080");
+    }
+
+    /// Serve one session that splits the audio in two parts: one cut at a
+    /// pause, answered only after the client's commit, and the rest.
+    /// Returns the endpoint and the PCM bytes received.
+    async fn mock_server() -> (String, tokio::task::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let send = |event: serde_json::Value| Message::Text(event.to_string());
+
+            let mut received = 0;
+            while let Some(Ok(Message::Text(text))) = socket.next().await {
+                let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+                match event["type"].as_str().unwrap() {
+                    "input_audio_buffer.append" => {
+                        let audio = event["audio"].as_str().unwrap();
+                        received += base64::engine::general_purpose::STANDARD
+                            .decode(audio)
+                            .unwrap()
+                            .len();
+                    }
+                    "input_audio_buffer.commit" => break,
+                    _ => {}
+                }
+            }
+
+            for event in [
+                json!({
+                    "type": "input_audio_buffer.speech_started",
+                    "item_id": "a",
+                    "audio_start_ms": 0,
+                }),
+                json!({
+                    "type": "input_audio_buffer.speech_stopped",
+                    "item_id": "a",
+                    "audio_end_ms": 600,
+                }),
+                json!({ "type": "input_audio_buffer.committed", "item_id": "a" }),
+                json!({
+                    "type": "conversation.item.input_audio_transcription.delta",
+                    "item_id": "a",
+                    "delta": "Good",
+                }),
+                // Everything sent is now transcribed, but the final commit
+                // has not been answered yet
+                json!({
+                    "type": "conversation.item.input_audio_transcription.completed",
+                    "item_id": "a",
+                    "transcript": "Good morning",
+                }),
+                json!({ "type": "input_audio_buffer.committed", "item_id": "b" }),
+                json!({
+                    "type": "conversation.item.input_audio_transcription.completed",
+                    "item_id": "b",
+                    "transcript": "there.",
+                }),
+            ] {
+                socket.send(send(event)).await.unwrap();
+            }
+            // The client closes the session once it has both parts
+            while let Some(Ok(message)) = socket.next().await {
+                if message.is_close() {
+                    break;
+                }
+            }
+            received
+        });
+        (endpoint, server)
+    }
+
+    #[tokio::test]
+    async fn test_streams_to_mock_server() {
+        let (endpoint, server) = mock_server().await;
+        let config = ProviderConfig {
+            api_key: Some("test".into()),
+            endpoint: Some(endpoint),
+            ..ProviderConfig::default()
+        };
+
+        let (audio_tx, audio_rx) = mpsc::unbounded_channel();
+        for _ in 0..10 {
+            audio_tx.send(vec![0.1; 1600]).unwrap();
+        }
+        drop(audio_tx);
+        let (hypothesis_tx, mut hypothesis_rx) = mpsc::unbounded_channel();
+
+        let result = OpenAiRealtimeProvider
+            .transcribe_stream(
+                audio_rx,
+                hypothesis_tx,
+                &config,
+                &StreamingConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.text, "Good morning there.");
+
+        let mut hypotheses = Vec::new();
+        while let Ok(hypothesis) = hypothesis_rx.try_recv() {
+            hypotheses.push(hypothesis);
+        }
+        assert_eq!(hypotheses.first().map(|h| h.text.as_str()), Some("Good"));
+        let finals: Vec<&str> = hypotheses
+            .iter()
+            .filter(|h| h.is_final)
+            .map(|h| h.text.as_str())
+            .collect();
+        assert_eq!(finals, vec!["Good morning", "there."]);
+
+        // One second of audio, sent as 24kHz 16-bit PCM
+        let received = server.await.unwrap();
+        assert!((received as i64 - 48000).abs() <= 2);
+    }
+}
@@ -3,14 +3,58 @@ use async_trait::async_trait;
 use reqwest::multipart;
 use std::time::Instant;
 
-use super::{ProviderConfig, ProviderId, SttProvider, TranscriptionResult};
-use crate::audio::encode_wav;
+use super::{
+    secs_to_ms, segments_confidence, ProviderConfig, ProviderId, Segment, SttProvider,
+    TranscriptionResult, Word,
+};
+
+/// The API rejects uploads over 25 MB, about 13 minutes of 16-bit 16kHz
+/// WAV. Five minutes is under 10 MB whatever the encoding, and shorter
+/// than a recording window so long recordings are cut at silence and can
+/// be sent in parallel.
+const MAX_CHUNK_MS: u64 = 5 * 60 * 1000;
 
 pub struct OpenAiWhisperProvider;
 
 #[derive(serde::Deserialize)]
 struct WhisperResponse {
     text: String,
+    /// Only in `verbose_json` responses.
+    #[serde(default)]
+    segments: Vec<WhisperSegment>,
+    #[serde(default)]
+    words: Vec<WhisperWord>,
+}
+
+#[derive(serde::Deserialize)]
+struct WhisperSegment {
+    text: String,
+    start: f64,
+    end: f64,
+    avg_logprob: Option<f64>,
+    no_speech_prob: Option<f64>,
+}
+
+impl WhisperSegment {
+    /// Chance the text is right: the mean token probability, discounted by
+    /// the chance the segment holds no speech at all.
+    fn confidence(&self) -> Option<f32> {
+        let speech = 1.0 - self.no_speech_prob.unwrap_or(0.0);
+        self.avg_logprob.map(|p| (p.exp() * speech) as f32)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct WhisperWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+/// The `gpt-4o` transcription models only answer in plain `json`, without
+/// timestamps.
+fn supports_timestamps(model: &str) -> bool {
+    !model.starts_with("gpt-")
 }
 
 #[async_trait]
@@ -27,6 +71,10 @@ impl SttProvider for OpenAiWhisperProvider {
         true
     }
 
+    fn max_chunk_ms(&self) -> Option<u64> {
+        Some(MAX_CHUNK_MS)
+    }
+
     async fn transcribe(
         &self,
         audio_data: &[f32],
@@ -39,13 +87,13 @@ impl SttProvider for OpenAiWhisperProvider {
 
         let start = Instant::now();
 
-        // Encode audio as WAV
-        let wav_bytes = encode_wav(audio_data, 16000);
+        let encoding = config.encoding;
+        let audio_bytes = encoding.encode(audio_data, 16000)?;
 
         // Build multipart form
-        let file_part = multipart::Part::bytes(wav_bytes)
-            .file_name("audio.wav")
-            .mime_str("audio/wav")?;
+        let file_part = multipart::Part::bytes(audio_bytes)
+            .file_name(format!("audio.{}", encoding.extension()))
+            .mime_str(encoding.mime_type())?;
 
         let model = config
             .model
@@ -53,9 +101,16 @@ impl SttProvider for OpenAiWhisperProvider {
             .unwrap_or("whisper-1")
             .to_string();
 
+        let timestamps = supports_timestamps(&model);
         let mut form = multipart::Form::new()
             .part("file", file_part)
             .text("model", model);
+        if timestamps {
+            form = form
+                .text("response_format", "verbose_json")
+                .text("timestamp_granularities[]", "segment")
+                .text("timestamp_granularities[]", "word");
+        }
 
         if let Some(lang) = &config.language {
             if lang != "auto" {
@@ -89,11 +144,36 @@ impl SttProvider for OpenAiWhisperProvider {
         let result: WhisperResponse = response.json().await?;
         let duration_ms = start.elapsed().as_millis() as u64;
 
+        let segments: Vec<Segment> = result
+            .segments
+            .into_iter()
+            .map(|segment| Segment {
+                confidence: segment.confidence(),
+                text: segment.text.trim().to_string(),
+                start_ms: secs_to_ms(segment.start),
+                end_ms: secs_to_ms(segment.end),
+            })
+            .collect();
+
         Ok(TranscriptionResult {
             text: result.text,
             provider: ProviderId::OpenAiWhisper,
             duration_ms,
             language: config.language.clone(),
+            trimmed_ms: 0,
+            confidence: segments_confidence(&segments),
+            alternatives: Vec::new(),
+            segments,
+            words: result
+                .words
+                .into_iter()
+                .map(|word| Word {
+                    text: word.word,
+                    start_ms: secs_to_ms(word.start),
+                    end_ms: secs_to_ms(word.end),
+                    confidence: None,
+                })
+                .collect(),
         })
     }
 }
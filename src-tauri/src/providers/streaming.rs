@@ -0,0 +1,310 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use super::{weighted_confidence, ProviderConfig, SttProvider, TranscriptionResult};
+use crate::audio::rms;
+
+/// Sample rate of the streamed audio.
+const SAMPLE_RATE: usize = 16000;
+
+/// RMS level below which streamed audio counts as a pause.
+const PAUSE_THRESHOLD: f32 = 0.01;
+
+/// Segments are not cut shorter than this, so a breath does not split a
+/// sentence.
+const MIN_SEGMENT_MS: usize = 1500;
+
+/// A segment without a pause is cut here, so a final hypothesis still
+/// arrives during a long unbroken stretch of speech.
+const MAX_SEGMENT_MS: usize = 30_000;
+
+/// 16kHz mono audio as it is recorded; the stream ends with the recording.
+pub type AudioChunks = mpsc::UnboundedReceiver<Vec<f32>>;
+
+/// Where a streaming transcription sends its hypotheses.
+pub type HypothesisSender = mpsc::UnboundedSender<Hypothesis>;
+
+/// What has been recognised since the last final hypothesis. An interim
+/// hypothesis is replaced by the next one; a final one is settled, and the
+/// next hypothesis covers the audio after it. Emitted to the frontend as
+/// `partial-transcription`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hypothesis {
+    pub text: String,
+    pub is_final: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingConfig {
+    /// Transcribe while recording, showing words as they are recognised.
+    /// The audio is streamed as captured, without silence trimming or
+    /// cleanup; those only apply when the recording is transcribed whole.
+    pub enabled: bool,
+    /// For providers that only transcribe whole recordings: how often the
+    /// speech since the last pause is sent again for an interim hypothesis.
+    /// 0 waits for each pause, which costs no extra requests.
+    pub interim_interval_ms: u32,
+    /// Silence that ends a segment for those providers.
+    pub pause_ms: u32,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interim_interval_ms: 0,
+            pause_ms: 600,
+        }
+    }
+}
+
+fn ms_to_samples(ms: usize) -> usize {
+    ms * SAMPLE_RATE / 1000
+}
+
+/// Streaming adapter for providers that only transcribe complete audio:
+/// the audio is cut at pauses and each segment transcribed as soon as it
+/// ends, giving one final hypothesis per segment. Segments without speech
+/// are dropped.
+pub async fn transcribe_by_pauses<P: SttProvider + ?Sized>(
+    provider: &P,
+    mut audio: AudioChunks,
+    hypotheses: HypothesisSender,
+    config: &ProviderConfig,
+    streaming: &StreamingConfig,
+) -> Result<TranscriptionResult> {
+    let pause = ms_to_samples(streaming.pause_ms as usize);
+    let interim_interval = (streaming.interim_interval_ms > 0)
+        .then(|| Duration::from_millis(streaming.interim_interval_ms as u64));
+
+    let mut segment: Vec<f32> = Vec::new();
+    let mut quiet = 0;
+    let mut heard = false;
+    let mut last_interim = Instant::now();
+    let mut interim_len = 0;
+
+    let mut texts: Vec<String> = Vec::new();
+    let mut language = None;
+    let mut confidences = Vec::new();
+    let mut busy = Duration::ZERO;
+    let mut dropped = 0;
+    // Timing, from the start of the stream
+    let mut offset = 0;
+    let (mut segments, mut words) = (Vec::new(), Vec::new());
+
+    loop {
+        let chunk = audio.recv().await;
+        let ended = chunk.is_none();
+        if let Some(chunk) = chunk {
+            if rms(&chunk) < PAUSE_THRESHOLD {
+                quiet += chunk.len();
+            } else {
+                quiet = 0;
+                heard = true;
+            }
+            segment.extend_from_slice(&chunk);
+        }
+
+        let paused = heard && quiet >= pause && segment.len() >= ms_to_samples(MIN_SEGMENT_MS);
+        if ended || paused || segment.len() >= ms_to_samples(MAX_SEGMENT_MS) {
+            if heard {
+                let start = Instant::now();
+                let mut result = provider.transcribe(&segment, config).await?;
+                busy += start.elapsed();
+                let segment_start = (offset * 1000 / SAMPLE_RATE) as u64;
+                result.map_times(|ms| ms + segment_start);
+                segments.append(&mut result.segments);
+                words.append(&mut result.words);
+                language = language.or(result.language);
+                if let Some(confidence) = result.confidence {
+                    confidences.push((confidence, segment.len() as u64));
+                }
+                let _ = hypotheses.send(Hypothesis {
+                    text: result.text.clone(),
+                    is_final: true,
+                });
+                texts.push(result.text);
+            } else {
+                dropped += segment.len();
+            }
+            offset += segment.len();
+            segment.clear();
+            (quiet, heard, interim_len) = (0, false, 0);
+            last_interim = Instant::now();
+        } else if let Some(interval) = interim_interval {
+            // Only resend once there is new speech to recognise
+            if heard
+                && quiet == 0
+                && segment.len() > interim_len
+                && last_interim.elapsed() >= interval
+            {
+                let start = Instant::now();
+                let result = provider.transcribe(&segment, config).await?;
+                busy += start.elapsed();
+                let _ = hypotheses.send(Hypothesis {
+                    text: result.text,
+                    is_final: false,
+                });
+                interim_len = segment.len();
+                last_interim = Instant::now();
+            }
+        }
+
+        if ended {
+            break;
+        }
+    }
+
+    let texts: Vec<&str> = texts
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect();
+    Ok(TranscriptionResult {
+        text: texts.join(" "),
+        provider: provider.id(),
+        duration_ms: busy.as_millis() as u64,
+        language,
+        trimmed_ms: (dropped * 1000 / SAMPLE_RATE) as u64,
+        segments,
+        words,
+        confidence: weighted_confidence(confidences),
+        alternatives: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ProviderId, Segment};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Names each request after its length in whole seconds, as one
+    /// segment covering all of it.
+    #[derive(Default)]
+    struct LengthProvider {
+        requests: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl SttProvider for LengthProvider {
+        fn id(&self) -> ProviderId {
+            ProviderId::LocalWhisper
+        }
+
+        fn name(&self) -> &str {
+            "Length"
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        async fn transcribe(
+            &self,
+            audio_data: &[f32],
+            _config: &ProviderConfig,
+        ) -> Result<TranscriptionResult> {
+            self.requests.lock().unwrap().push(audio_data.len());
+            let text = format!("{}s", audio_data.len() / SAMPLE_RATE);
+            Ok(TranscriptionResult {
+                text: text.clone(),
+                provider: self.id(),
+                duration_ms: 0,
+                language: None,
+                trimmed_ms: 0,
+                segments: vec![Segment {
+                    text,
+                    start_ms: 0,
+                    end_ms: (audio_data.len() * 1000 / SAMPLE_RATE) as u64,
+                    confidence: None,
+                }],
+                words: Vec::new(),
+                confidence: None,
+                alternatives: Vec::new(),
+            })
+        }
+    }
+
+    fn speech(ms: usize) -> Vec<f32> {
+        (0..ms_to_samples(ms))
+            .map(|i| 0.3 * (i as f32 * 0.1).sin())
+            .collect()
+    }
+
+    fn silence(ms: usize) -> Vec<f32> {
+        vec![0.0; ms_to_samples(ms)]
+    }
+
+    /// Send `audio` in 100ms chunks and run the adapter to the end.
+    async fn stream(
+        provider: &LengthProvider,
+        audio: Vec<f32>,
+        streaming: StreamingConfig,
+    ) -> (TranscriptionResult, Vec<Hypothesis>) {
+        let (audio_tx, audio_rx) = mpsc::unbounded_channel();
+        for chunk in audio.chunks(ms_to_samples(100)) {
+            audio_tx.send(chunk.to_vec()).unwrap();
+        }
+        drop(audio_tx);
+
+        let (hypothesis_tx, mut hypothesis_rx) = mpsc::unbounded_channel();
+        let result = transcribe_by_pauses(
+            provider,
+            audio_rx,
+            hypothesis_tx,
+            &ProviderConfig::default(),
+            &streaming,
+        )
+        .await
+        .unwrap();
+        let mut hypotheses = Vec::new();
+        while let Ok(hypothesis) = hypothesis_rx.try_recv() {
+            hypotheses.push(hypothesis);
+        }
+        (result, hypotheses)
+    }
+
+    #[tokio::test]
+    async fn test_segments_at_pauses() {
+        let provider = LengthProvider::default();
+        let audio = [speech(2000), silence(1000), speech(3000), silence(300)].concat();
+        let (result, hypotheses) = stream(&provider, audio, StreamingConfig::default()).await;
+
+        // 2s of speech ends once 600ms of the pause have passed; the rest of
+        // the pause starts the next segment
+        let finals: Vec<&str> = hypotheses.iter().map(|h| h.text.as_str()).collect();
+        assert_eq!(finals, vec!["2s", "3s"]);
+        assert!(hypotheses.iter().all(|h| h.is_final));
+        assert_eq!(result.text, "2s 3s");
+        let times: Vec<(u64, u64)> = result
+            .segments
+            .iter()
+            .map(|s| (s.start_ms, s.end_ms))
+            .collect();
+        assert_eq!(times, vec![(0, 2600), (2600, 6300)]);
+    }
+
+    #[tokio::test]
+    async fn test_silence_is_not_sent() {
+        let provider = LengthProvider::default();
+        let (result, hypotheses) =
+            stream(&provider, silence(3000), StreamingConfig::default()).await;
+        assert!(hypotheses.is_empty());
+        assert!(provider.requests.lock().unwrap().is_empty());
+        assert_eq!(result.text, "");
+        assert_eq!(result.trimmed_ms, 3000);
+    }
+
+    #[tokio::test]
+    async fn test_long_speech_is_cut() {
+        let provider = LengthProvider::default();
+        let (_, hypotheses) = stream(&provider, speech(45_000), StreamingConfig::default()).await;
+        let finals: Vec<&str> = hypotheses.iter().map(|h| h.text.as_str()).collect();
+        assert_eq!(finals, vec!["30s", "15s"]);
+    }
+}
@@ -4,7 +4,18 @@ use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 
-use crate::providers::{ProviderConfig, ProviderId};
+use crate::audio::capture::CaptureConfig;
+use crate::audio::dsp::DspConfig;
+use crate::audio::limit::MaxDurationConfig;
+use crate::audio::preroll::PreRollConfig;
+use crate::audio::resampler::ResampleQuality;
+use crate::audio::spill::SpillConfig;
+use crate::audio::trim::SilenceTrimConfig;
+use crate::audio::vad::VadConfig;
+use crate::batch::folder::BatchConfig;
+use crate::providers::chunking::ChunkingConfig;
+use crate::providers::streaming::StreamingConfig;
+use crate::providers::{ConfidenceConfig, ProviderConfig, ProviderId};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum InteractionMode {
@@ -17,6 +28,9 @@ pub struct AppSettings {
     pub active_provider: ProviderId,
     pub interaction_mode: InteractionMode,
     pub hotkey: String,
+    /// Pauses and resumes the current recording without ending it.
+    #[serde(default)]
+    pub pause_hotkey: Option<String>,
     pub language: String,
     pub provider_configs: HashMap<ProviderId, ProviderConfig>,
     pub local_whisper_model_path: Option<String>,
@@ -24,6 +38,49 @@ pub struct AppSettings {
     pub show_overlay: bool,
     #[serde(default)]
     pub input_device: Option<String>,
+    /// Continue on the default device if the input device disconnects
+    /// mid-recording.
+    #[serde(default = "default_true")]
+    pub device_failover: bool,
+    /// Capture format per input device name.
+    #[serde(default)]
+    pub device_configs: HashMap<String, CaptureConfig>,
+    /// In Toggle mode, stop automatically once the speaker has gone quiet.
+    #[serde(default)]
+    pub vad_auto_stop: bool,
+    #[serde(default)]
+    pub vad: VadConfig,
+    /// Stop recordings that run past a maximum length.
+    #[serde(default)]
+    pub max_duration: MaxDurationConfig,
+    /// Filtering and gain applied before transcription.
+    #[serde(default)]
+    pub dsp: DspConfig,
+    #[serde(default)]
+    pub silence_trim: SilenceTrimConfig,
+    #[serde(default)]
+    pub resample_quality: ResampleQuality,
+    #[serde(default)]
+    pub preroll: PreRollConfig,
+    /// Move long recordings from memory to a temporary file.
+    #[serde(default)]
+    pub spill: SpillConfig,
+    /// How audio longer than a provider accepts is split up.
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+    /// Transcription while recording.
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    /// When to ask before pasting an uncertain transcription.
+    #[serde(default)]
+    pub confidence: ConfidenceConfig,
+    /// Transcripts written for audio files in a folder.
+    #[serde(default)]
+    pub batch: BatchConfig,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for AppSettings {
@@ -32,12 +89,27 @@ impl Default for AppSettings {
             active_provider: ProviderId::OpenAiWhisper,
             interaction_mode: InteractionMode::Toggle,
             hotkey: "CommandOrControl+Shift+Space".into(),
+            pause_hotkey: None,
             language: "auto".into(),
             provider_configs: HashMap::new(),
             local_whisper_model_path: None,
             auto_paste: true,
             show_overlay: true,
             input_device: None,
+            device_failover: true,
+            device_configs: HashMap::new(),
+            vad_auto_stop: false,
+            vad: VadConfig::default(),
+            max_duration: MaxDurationConfig::default(),
+            dsp: DspConfig::default(),
+            silence_trim: SilenceTrimConfig::default(),
+            resample_quality: ResampleQuality::default(),
+            preroll: PreRollConfig::default(),
+            spill: SpillConfig::default(),
+            chunking: ChunkingConfig::default(),
+            streaming: StreamingConfig::default(),
+            confidence: ConfidenceConfig::default(),
+            batch: BatchConfig::default(),
         }
     }
 }
@@ -66,4 +138,14 @@ impl AppSettings {
             .cloned()
             .unwrap_or_default()
     }
+
+    /// Capture format for the selected input device. The system default
+    /// device always uses its default format.
+    pub fn get_capture_config(&self) -> CaptureConfig {
+        self.input_device
+            .as_ref()
+            .and_then(|name| self.device_configs.get(name))
+            .cloned()
+            .unwrap_or_default()
+    }
 }
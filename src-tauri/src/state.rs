@@ -1,27 +1,118 @@
 use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
 
-use crate::audio::AudioRecorder;
+use crate::audio::engine::AudioEngine;
+use crate::audio::spill::AudioReader;
+use crate::batch::BatchJobs;
 use crate::history::TranscriptionHistory;
-use crate::providers::ProviderManager;
+use crate::providers::chunking::{self, samples_to_ms, Windows, WINDOW_MS};
+use crate::providers::{ProviderManager, TranscriptionResult};
 use crate::settings::AppSettings;
 
 pub struct AppState {
-    pub recorder: Arc<Mutex<AudioRecorder>>,
+    pub recorder: AudioEngine,
     pub provider_manager: Arc<Mutex<ProviderManager>>,
     pub settings: Arc<Mutex<AppSettings>>,
     pub history: Arc<Mutex<TranscriptionHistory>>,
     pub is_recording: Arc<Mutex<bool>>,
+    pub batch: Mutex<BatchJobs>,
+    /// Transcription running alongside the current recording.
+    pub live: Mutex<Option<JoinHandle<anyhow::Result<TranscriptionResult>>>>,
 }
 
 impl AppState {
-    pub fn new(settings: AppSettings) -> Self {
+    pub fn new(settings: AppSettings, recorder: AudioEngine) -> Self {
         let provider_manager = ProviderManager::new(&settings);
         Self {
-            recorder: Arc::new(Mutex::new(AudioRecorder::new())),
+            recorder,
             provider_manager: Arc::new(Mutex::new(provider_manager)),
             settings: Arc::new(Mutex::new(settings)),
             history: Arc::new(Mutex::new(TranscriptionHistory::new(100))),
             is_recording: Arc::new(Mutex::new(false)),
+            batch: Mutex::new(BatchJobs::default()),
+            live: Mutex::new(None),
         }
     }
+
+    /// Wait for the transcription that ran alongside the recording that
+    /// just stopped. `None` if there was none, it failed or it heard
+    /// nothing, in which case the recording is transcribed as a whole: the
+    /// live path has no silence trim or cleanup, so quiet speech it missed
+    /// may still be found.
+    pub async fn finish_live_transcription(&self) -> Option<TranscriptionResult> {
+        let task = self.live.lock().unwrap().take()?;
+        match task.await {
+            Ok(Ok(result)) if result.text.trim().is_empty() => {
+                log::info!("Live transcription heard nothing, transcribing the recording");
+                None
+            }
+            Ok(Ok(result)) => Some(result),
+            Ok(Err(e)) => {
+                log::warn!("Live transcription failed: {}", e);
+                None
+            }
+            Err(e) => {
+                log::warn!("Live transcription stopped: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Drop the transcription running alongside a recording that is not
+    /// transcribed, so it does not carry over to the next one.
+    pub fn abort_live_transcription(&self) {
+        if let Some(task) = self.live.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    /// Clean up, trim and transcribe a 16kHz recording with the active
+    /// provider, a window at a time so a long one is never in memory
+    /// whole. `None` if it holds no speech.
+    pub async fn transcribe(
+        &self,
+        audio: AudioReader<'_>,
+    ) -> anyhow::Result<Option<TranscriptionResult>> {
+        let (provider, config, chunking, silence_trim, dsp) = {
+            let pm = self.provider_manager.lock().unwrap();
+            let settings = self.settings.lock().unwrap();
+            let provider = pm.get_active();
+            let config = settings.get_provider_config(&provider.id());
+            (
+                provider,
+                config,
+                settings.chunking.clone(),
+                settings.silence_trim.clone(),
+                settings.dsp.clone(),
+            )
+        };
+
+        let mut windows = Windows::new(audio, WINDOW_MS);
+        let mut results = Vec::new();
+        let (mut offset, mut trimmed_ms) = (0, 0);
+        while let Some(window) = windows.next_window()? {
+            let len = window.len();
+            let offset_ms = samples_to_ms(offset);
+            offset += len;
+
+            // Clean up first, so rumble doesn't read as speech and quiet
+            // speech has been raised before it is compared to the threshold
+            let window = crate::audio::dsp::prepare_for_upload(window, &dsp);
+            let (window, trim) = crate::audio::trim::trim_for_upload(window, &silence_trim);
+            trimmed_ms += trim.removed_ms();
+            if window.is_empty() {
+                continue;
+            }
+
+            let mut result = chunking::transcribe(&provider, &window, &config, &chunking).await?;
+            result.map_times(|ms| offset_ms + trim.original_ms(ms));
+            results.push((result, len));
+        }
+
+        let mut result = chunking::join_windows(results);
+        if let Some(result) = &mut result {
+            result.trimmed_ms = trimmed_ms;
+        }
+        Ok(result)
+    }
 }
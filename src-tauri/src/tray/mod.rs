@@ -5,6 +5,8 @@ use tauri::{
     AppHandle, Manager, Runtime,
 };
 
+const TRAY_ID: &str = "main";
+
 fn create_default_icon() -> Image<'static> {
     // 32x32 RGBA solid blue icon as fallback
     let size = 32u32;
@@ -15,6 +17,23 @@ fn create_default_icon() -> Image<'static> {
     Image::new_owned(rgba, size, size)
 }
 
+fn create_armed_icon() -> Image<'static> {
+    // Default icon with a red dot in the bottom-right corner
+    let size = 32u32;
+    let mut rgba = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let (dx, dy) = (x as i32 - 24, y as i32 - 24);
+            if dx * dx + dy * dy <= 36 {
+                rgba.extend_from_slice(&[220, 38, 38, 255]);
+            } else {
+                rgba.extend_from_slice(&[70, 130, 180, 255]);
+            }
+        }
+    }
+    Image::new_owned(rgba, size, size)
+}
+
 pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     let show_item = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
@@ -27,7 +46,7 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
 
     let icon = create_default_icon();
 
-    TrayIconBuilder::new()
+    TrayIconBuilder::with_id(TRAY_ID)
         .icon(icon)
         .menu(&menu)
         .tooltip("Whisper - Speech to Text")
@@ -49,3 +68,16 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
 
     Ok(())
 }
+
+/// Show whether the microphone is kept open for pre-roll.
+pub fn set_armed<R: Runtime>(app: &AppHandle<R>, armed: bool) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let (icon, tooltip) = if armed {
+            (create_armed_icon(), "Whisper - Microphone armed")
+        } else {
+            (create_default_icon(), "Whisper - Speech to Text")
+        };
+        let _ = tray.set_icon(Some(icon));
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
@@ -3,9 +3,14 @@ import { useAppStore } from "./stores/useAppStore";
 import { setupEventListeners } from "./lib/events";
 import {
   DEFAULT_HOTKEY,
+  copyToClipboard,
+  getBatchStatus,
   getSettings,
   getHistory,
   getProviders,
+  pasteConfirmed,
+  pauseRecording,
+  resumeRecording,
   startRecording,
   stopRecordingAndTranscribe,
 } from "./lib/commands";
@@ -16,19 +21,31 @@ import { HotkeyDisplay } from "./components/HotkeyDisplay";
 
 function App() {
   const setRecording = useAppStore((s) => s.setRecording);
+  const setPaused = useAppStore((s) => s.setPaused);
   const setTranscribing = useAppStore((s) => s.setTranscribing);
+  const setAudioLevel = useAppStore((s) => s.setAudioLevel);
+  const setMaxDurationWarning = useAppStore((s) => s.setMaxDurationWarning);
   const setCurrentTranscription = useAppStore((s) => s.setCurrentTranscription);
+  const addHypothesis = useAppStore((s) => s.addHypothesis);
+  const clearLiveTranscription = useAppStore((s) => s.clearLiveTranscription);
+  const setPendingConfirmation = useAppStore((s) => s.setPendingConfirmation);
   const setError = useAppStore((s) => s.setError);
   const setSettings = useAppStore((s) => s.setSettings);
   const setHistory = useAppStore((s) => s.setHistory);
   const setProviders = useAppStore((s) => s.setProviders);
+  const setInputDevices = useAppStore((s) => s.setInputDevices);
   const addHistory = useAppStore((s) => s.addHistory);
+  const setBatchJobs = useAppStore((s) => s.setBatchJobs);
+  const updateBatchJob = useAppStore((s) => s.updateBatchJob);
   const activeView = useAppStore((s) => s.activeView);
   const setActiveView = useAppStore((s) => s.setActiveView);
   const isRecording = useAppStore((s) => s.isRecording);
+  const isPaused = useAppStore((s) => s.isPaused);
   const isTranscribing = useAppStore((s) => s.isTranscribing);
   const error = useAppStore((s) => s.error);
   const currentTranscription = useAppStore((s) => s.currentTranscription);
+  const liveTranscription = useAppStore((s) => s.liveTranscription);
+  const pendingConfirmation = useAppStore((s) => s.pendingConfirmation);
   const settings = useAppStore((s) => s.settings);
 
   const handleMicClick = async () => {
@@ -44,25 +61,94 @@ function App() {
     }
   };
 
+  const handlePaste = async (text: string) => {
+    try {
+      setPendingConfirmation(null);
+      await pasteConfirmed(text);
+    } catch (e) {
+      setError(`Paste failed: ${e}`);
+    }
+  };
+
+  const handleCopy = async (text: string) => {
+    try {
+      await copyToClipboard(text);
+      setPendingConfirmation(null);
+    } catch (e) {
+      setError(`Copy failed: ${e}`);
+    }
+  };
+
+  const handlePauseClick = async () => {
+    try {
+      if (isPaused) {
+        await resumeRecording();
+      } else {
+        await pauseRecording();
+      }
+    } catch (e) {
+      console.error("Pause toggle failed:", e);
+    }
+  };
+
   // Initialize app
   useEffect(() => {
     getSettings().then(setSettings);
     getHistory().then(setHistory);
     getProviders().then(setProviders);
+    getBatchStatus().then(setBatchJobs);
 
     const cleanup = setupEventListeners({
       onRecordingStarted: () => {
         setRecording(true);
+        setPaused(false);
+        setMaxDurationWarning(null);
+        clearLiveTranscription();
+        setPendingConfirmation(null);
         setError(null);
       },
       onRecordingStopped: () => {
         setRecording(false);
+        setPaused(false);
+        setAudioLevel(null);
+        setMaxDurationWarning(null);
+      },
+      onRecordingPaused: () => {
+        setPaused(true);
+        setAudioLevel(null);
+      },
+      onRecordingResumed: () => {
+        setPaused(false);
+      },
+      onAudioLevel: (level) => {
+        setAudioLevel(level);
+      },
+      onMaxDurationWarning: (warning) => {
+        setMaxDurationWarning(warning);
+      },
+      onStreamError: (event) => {
+        if (!event.device_lost) {
+          console.warn("Audio stream error:", event.message);
+          return;
+        }
+        setError(
+          event.recovered_on
+            ? `Input device disconnected, continuing on ${event.recovered_on}`
+            : `Input device disconnected: ${event.message}`
+        );
+      },
+      onInputDevicesChanged: (devices) => {
+        setInputDevices(devices);
       },
       onTranscribing: () => {
         setTranscribing(true);
       },
+      onPartialTranscription: (hypothesis) => {
+        addHypothesis(hypothesis);
+      },
       onTranscriptionComplete: (result) => {
         setTranscribing(false);
+        clearLiveTranscription();
         setCurrentTranscription(result.text);
         addHistory({
           id: crypto.randomUUID(),
@@ -71,11 +157,25 @@ function App() {
           timestamp: new Date().toISOString(),
           duration_ms: result.duration_ms,
           language: result.language,
+          trimmed_ms: result.trimmed_ms,
+          segments: result.segments,
+          words: result.words,
+          confidence: result.confidence,
         });
       },
+      onLowConfidence: (result) => {
+        setPendingConfirmation(result);
+        setActiveView("home");
+      },
+      onBatchProgress: (progress) => {
+        updateBatchJob(progress);
+      },
       onError: (err) => {
         setRecording(false);
+        setPaused(false);
         setTranscribing(false);
+        setAudioLevel(null);
+        clearLiveTranscription();
         setError(err);
       },
     });
@@ -170,7 +270,9 @@ function App() {
               <div className="flex flex-col items-center gap-2">
                 <p className="text-sm text-zinc-400">
                   {isRecording
-                    ? "Recording... click to stop"
+                    ? isPaused
+                      ? "Paused... click to stop and transcribe"
+                      : "Recording... click to stop"
                     : isTranscribing
                       ? "Transcribing..."
                       : "Click or press hotkey to record"}
@@ -180,9 +282,76 @@ function App() {
                     hotkey={settings?.hotkey || DEFAULT_HOTKEY}
                   />
                 )}
+                {isRecording && (
+                  <button
+                    onClick={handlePauseClick}
+                    className="px-3 py-1 text-xs font-medium rounded-lg bg-zinc-800 hover:bg-zinc-700 text-zinc-300 transition-colors"
+                  >
+                    {isPaused ? "Resume" : "Pause"}
+                  </button>
+                )}
               </div>
             </div>
 
+            {/* Words recognised while recording */}
+            {(isRecording || isTranscribing) &&
+              (liveTranscription.final || liveTranscription.interim) && (
+                <div className="w-full max-w-sm bg-zinc-800/60 rounded-lg p-4">
+                  <p className="text-xs text-zinc-500 mb-2">Live transcription</p>
+                  <p className="text-sm text-zinc-200 leading-relaxed">
+                    {liveTranscription.final}
+                    {liveTranscription.final && liveTranscription.interim && " "}
+                    <span className="text-zinc-500">{liveTranscription.interim}</span>
+                  </p>
+                </div>
+              )}
+
+            {/* Held back from auto-paste */}
+            {pendingConfirmation && (
+              <div className="w-full max-w-sm bg-amber-950/40 border border-amber-800/50 rounded-lg p-4">
+                <p className="text-xs text-amber-400 mb-2">
+                  Not pasted: only{" "}
+                  {Math.round((pendingConfirmation.confidence ?? 0) * 100)}% confident.
+                  Paste the right reading?
+                </p>
+                <div className="flex flex-col gap-2">
+                  {[
+                    { text: pendingConfirmation.text, confidence: pendingConfirmation.confidence },
+                    ...pendingConfirmation.alternatives,
+                  ].map((reading, i) => (
+                    <div key={i} className="flex items-start gap-2">
+                      <p className="flex-1 text-sm text-zinc-200 leading-relaxed">
+                        {reading.text}
+                        {reading.confidence !== null && (
+                          <span className="ml-1 text-xs text-zinc-500">
+                            {Math.round(reading.confidence * 100)}%
+                          </span>
+                        )}
+                      </p>
+                      <button
+                        onClick={() => handlePaste(reading.text)}
+                        className="px-2 py-0.5 text-xs font-medium rounded bg-amber-700 hover:bg-amber-600 text-zinc-100 transition-colors"
+                      >
+                        Paste
+                      </button>
+                      <button
+                        onClick={() => handleCopy(reading.text)}
+                        className="px-2 py-0.5 text-xs font-medium rounded bg-zinc-800 hover:bg-zinc-700 text-zinc-300 transition-colors"
+                      >
+                        Copy
+                      </button>
+                    </div>
+                  ))}
+                </div>
+                <button
+                  onClick={() => setPendingConfirmation(null)}
+                  className="mt-3 text-xs text-zinc-500 hover:text-zinc-300"
+                >
+                  Dismiss
+                </button>
+              </div>
+            )}
+
             {/* Last transcription */}
             {currentTranscription && (
               <div className="w-full max-w-sm bg-zinc-800/60 rounded-lg p-4">
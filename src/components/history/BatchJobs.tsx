@@ -0,0 +1,45 @@
+import { useAppStore } from "../../stores/useAppStore";
+import { cancelBatch } from "../../lib/commands";
+
+export function BatchJobs() {
+  const jobs = useAppStore((s) => s.batchJobs);
+  const visible = jobs.filter((job) => !job.finished || !job.watching);
+
+  if (visible.length === 0) return null;
+
+  return (
+    <div className="flex flex-col gap-2 mb-3">
+      {visible.map((job) => (
+        <div
+          key={job.watching ? "watch" : "folder"}
+          className="flex flex-col gap-1 bg-zinc-800/60 rounded-lg p-3"
+        >
+          <div className="flex items-center justify-between gap-3">
+            <span className="text-xs text-zinc-300 truncate" title={job.folder}>
+              {job.watching ? "Watching" : "Folder"} {job.folder}
+            </span>
+            {!job.watching && !job.finished && (
+              <button
+                onClick={() => cancelBatch()}
+                className="text-xs text-zinc-500 hover:text-red-400 transition-colors"
+              >
+                Cancel
+              </button>
+            )}
+          </div>
+          <span className="text-xs text-zinc-500">
+            {job.done} of {job.total} transcribed
+            {job.failed > 0 && <> &middot; {job.failed} failed</>}
+            {job.current && <> &middot; {job.current}</>}
+            {job.finished && !job.watching && <> &middot; finished</>}
+          </span>
+          {job.last_error && (
+            <span className="text-xs text-red-400 truncate" title={job.last_error}>
+              {job.last_error}
+            </span>
+          )}
+        </div>
+      ))}
+    </div>
+  );
+}
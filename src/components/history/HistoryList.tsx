@@ -1,10 +1,21 @@
-import { useEffect } from "react";
+import { useEffect, useState } from "react";
 import { useAppStore } from "../../stores/useAppStore";
-import { getHistory, clearHistory } from "../../lib/commands";
+import {
+  getHistory,
+  clearHistory,
+  pickAudioFile,
+  pickFolder,
+  startBatch,
+  transcribeFile,
+} from "../../lib/commands";
+import { BatchJobs } from "./BatchJobs";
+import { RecoveredRecordings } from "./RecoveredRecordings";
 
 export function HistoryList() {
   const history = useAppStore((s) => s.history);
   const setHistory = useAppStore((s) => s.setHistory);
+  const [transcribingFile, setTranscribingFile] = useState(false);
+  const [fileError, setFileError] = useState<string | null>(null);
 
   useEffect(() => {
     getHistory().then(setHistory);
@@ -14,6 +25,30 @@ export function HistoryList() {
     await navigator.clipboard.writeText(text);
   };
 
+  const handleTranscribeFile = async () => {
+    setFileError(null);
+    try {
+      const path = await pickAudioFile();
+      if (!path) return;
+      setTranscribingFile(true);
+      await transcribeFile(path);
+    } catch (e) {
+      setFileError(String(e));
+    } finally {
+      setTranscribingFile(false);
+    }
+  };
+
+  const handleTranscribeFolder = async () => {
+    setFileError(null);
+    try {
+      const folder = await pickFolder();
+      if (folder) await startBatch(folder);
+    } catch (e) {
+      setFileError(String(e));
+    }
+  };
+
   const handleClear = async () => {
     await clearHistory();
     setHistory([]);
@@ -23,17 +58,35 @@ export function HistoryList() {
     <div className="flex flex-col h-full">
       <div className="flex items-center justify-between p-6 pb-3">
         <h2 className="text-lg font-semibold text-zinc-100">History</h2>
-        {history.length > 0 && (
+        <div className="flex gap-4">
           <button
-            onClick={handleClear}
-            className="text-xs text-zinc-500 hover:text-red-400 transition-colors"
+            onClick={handleTranscribeFile}
+            disabled={transcribingFile}
+            className="text-xs text-blue-400 hover:text-blue-300 disabled:text-zinc-600 transition-colors"
           >
-            Clear All
+            {transcribingFile ? "Transcribing..." : "Transcribe File"}
           </button>
-        )}
+          <button
+            onClick={handleTranscribeFolder}
+            className="text-xs text-blue-400 hover:text-blue-300 transition-colors"
+          >
+            Transcribe Folder
+          </button>
+          {history.length > 0 && (
+            <button
+              onClick={handleClear}
+              className="text-xs text-zinc-500 hover:text-red-400 transition-colors"
+            >
+              Clear All
+            </button>
+          )}
+        </div>
       </div>
+      {fileError && <p className="px-6 pb-3 text-xs text-red-400">{fileError}</p>}
 
       <div className="flex-1 overflow-y-auto px-6 pb-6">
+        <RecoveredRecordings />
+        <BatchJobs />
         {history.length === 0 ? (
           <div className="flex items-center justify-center h-32 text-zinc-500 text-sm">
             No transcriptions yet
@@ -53,6 +106,20 @@ export function HistoryList() {
                     <span>{entry.provider}</span>
                     <span>&middot;</span>
                     <span>{(entry.duration_ms / 1000).toFixed(1)}s</span>
+                    {entry.trimmed_ms > 0 && (
+                      <>
+                        <span>&middot;</span>
+                        <span>
+                          {(entry.trimmed_ms / 1000).toFixed(1)}s silence trimmed
+                        </span>
+                      </>
+                    )}
+                    {entry.confidence != null && (
+                      <>
+                        <span>&middot;</span>
+                        <span>{Math.round(entry.confidence * 100)}% confident</span>
+                      </>
+                    )}
                     <span>&middot;</span>
                     <span>
                       {new Date(entry.timestamp).toLocaleTimeString()}
@@ -0,0 +1,65 @@
+import { useEffect, useState } from "react";
+import type { RecoveredRecording } from "../../types";
+import {
+  discardRecoveredRecording,
+  listRecoveredRecordings,
+  transcribeRecoveredRecording,
+} from "../../lib/commands";
+
+export function RecoveredRecordings() {
+  const [recordings, setRecordings] = useState<RecoveredRecording[]>([]);
+  const [busy, setBusy] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    listRecoveredRecordings().then(setRecordings);
+  }, []);
+
+  const handle = async (name: string, action: (name: string) => Promise<unknown>) => {
+    setBusy(name);
+    setError(null);
+    try {
+      await action(name);
+      setRecordings((current) => current.filter((r) => r.name !== name));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  if (recordings.length === 0) return null;
+
+  return (
+    <div className="flex flex-col gap-2 mb-3 bg-amber-950/40 border border-amber-800/50 rounded-lg p-3">
+      <p className="text-xs text-amber-300">
+        These recordings were not transcribed, because the app closed or transcription failed.
+      </p>
+      {recordings.map((recording) => (
+        <div key={recording.name} className="flex items-center justify-between">
+          <span className="text-xs text-zinc-300">
+            {new Date(recording.recorded_at).toLocaleString()} &middot;{" "}
+            {Math.round(recording.duration_ms / 60000)} min
+          </span>
+          <div className="flex gap-3">
+            <button
+              onClick={() => handle(recording.name, transcribeRecoveredRecording)}
+              disabled={busy !== null}
+              className="text-xs text-blue-400 hover:text-blue-300 disabled:text-zinc-600 transition-colors"
+            >
+              {busy === recording.name ? "Transcribing..." : "Transcribe"}
+            </button>
+            <button
+              onClick={() => handle(recording.name, discardRecoveredRecording)}
+              disabled={busy !== null}
+              className="text-xs text-zinc-500 hover:text-red-400 disabled:text-zinc-600 transition-colors"
+            >
+              Discard
+            </button>
+          </div>
+        </div>
+      ))}
+      {error && <p className="text-xs text-red-400">{error}</p>}
+    </div>
+  );
+}
@@ -1,22 +1,38 @@
 import { useAppStore } from "../../stores/useAppStore";
+import type { AudioLevel } from "../../types";
 
 export function RecordingIndicator() {
   const isRecording = useAppStore((s) => s.isRecording);
+  const isPaused = useAppStore((s) => s.isPaused);
   const isTranscribing = useAppStore((s) => s.isTranscribing);
+  const audioLevel = useAppStore((s) => s.audioLevel);
+  const maxDurationWarning = useAppStore((s) => s.maxDurationWarning);
 
   if (!isRecording && !isTranscribing) return null;
 
   return (
     <div className="flex items-center gap-3 px-4 py-3 rounded-xl bg-zinc-800/80 backdrop-blur">
-      {isRecording && (
+      {isRecording && isPaused && (
+        <>
+          <span className="inline-flex rounded-full h-3 w-3 bg-amber-500" />
+          <span className="text-sm text-zinc-200 font-medium">Paused</span>
+        </>
+      )}
+      {isRecording && !isPaused && (
         <>
           <span className="relative flex h-3 w-3">
             <span className="animate-ping absolute inline-flex h-full w-full rounded-full bg-red-400 opacity-75" />
             <span className="relative inline-flex rounded-full h-3 w-3 bg-red-500" />
           </span>
           <span className="text-sm text-zinc-200 font-medium">Recording...</span>
+          {audioLevel && <LevelMeter level={audioLevel} />}
         </>
       )}
+      {isRecording && maxDurationWarning && (
+        <span className="text-xs text-amber-400">
+          Stops in {Math.ceil(maxDurationWarning.remaining_ms / 1000)}s
+        </span>
+      )}
       {isTranscribing && (
         <>
           <svg
@@ -46,3 +62,28 @@ export function RecordingIndicator() {
     </div>
   );
 }
+
+function LevelMeter({ level }: { level: AudioLevel }) {
+  // Map -60..0 dBFS onto the bar width
+  const db = 20 * Math.log10(Math.max(level.rms, 1e-6));
+  const width = Math.min(100, Math.max(0, ((db + 60) / 60) * 100));
+
+  return (
+    <div className="flex items-center gap-2">
+      <div className="w-24 h-1.5 rounded-full bg-zinc-700 overflow-hidden">
+        <div
+          className={`h-full transition-all duration-75 ${
+            level.clipping ? "bg-red-500" : "bg-green-500"
+          }`}
+          style={{ width: `${width}%` }}
+        />
+      </div>
+      {level.clipping && (
+        <span className="text-xs text-red-400">Clipping</span>
+      )}
+      {level.peak < 0.001 && (
+        <span className="text-xs text-amber-400">No input</span>
+      )}
+    </div>
+  );
+}
@@ -1,21 +1,47 @@
 import { useEffect, useRef, useState } from "react";
 import { useAppStore } from "../../stores/useAppStore";
-import { DEFAULT_HOTKEY, getProviders, listInputDevices, saveSettings } from "../../lib/commands";
+import {
+  DEFAULT_HOTKEY,
+  getAudioEncodings,
+  getInputCapabilities,
+  getProviders,
+  listInputDevices,
+  pickFolder,
+  saveSettings,
+  testInputDevice,
+} from "../../lib/commands";
 import { HotkeyDisplay } from "../HotkeyDisplay";
-import type { AppSettings, AudioDevice, ProviderId } from "../../types";
+import type {
+  AppSettings,
+  AudioEncoding,
+  CaptureConfig,
+  DeviceCapabilities,
+  DeviceTestReport,
+  ProviderId,
+  ResampleQuality,
+} from "../../types";
 
 export function SettingsPanel() {
   const settings = useAppStore((s) => s.settings);
   const setSettings = useAppStore((s) => s.setSettings);
   const providers = useAppStore((s) => s.providers);
   const setProviders = useAppStore((s) => s.setProviders);
+  const inputDevices = useAppStore((s) => s.inputDevices);
+  const setInputDevices = useAppStore((s) => s.setInputDevices);
 
   const [localSettings, setLocalSettings] = useState<AppSettings | null>(null);
   const [saving, setSaving] = useState(false);
   const [saved, setSaved] = useState(false);
   const [isRecordingHotkey, setIsRecordingHotkey] = useState(false);
   const hotkeyRef = useRef<HTMLDivElement>(null);
-  const [inputDevices, setInputDevices] = useState<AudioDevice[]>([]);
+  const [isRecordingPauseHotkey, setIsRecordingPauseHotkey] = useState(false);
+  const pauseHotkeyRef = useRef<HTMLDivElement>(null);
+  const [testingDevice, setTestingDevice] = useState(false);
+  const [deviceReport, setDeviceReport] = useState<DeviceTestReport | null>(null);
+  const [deviceTestError, setDeviceTestError] = useState<string | null>(null);
+  const [capabilities, setCapabilities] = useState<DeviceCapabilities | null>(null);
+  const [saveError, setSaveError] = useState<string | null>(null);
+  const [encodings, setEncodings] = useState<AudioEncoding[]>(["Wav", "Flac"]);
 
   useEffect(() => {
     if (settings) {
@@ -26,10 +52,24 @@ export function SettingsPanel() {
   useEffect(() => {
     getProviders().then(setProviders);
     listInputDevices().then(setInputDevices);
+    getAudioEncodings().then(setEncodings);
   }, []);
 
+  const selectedDevice = localSettings?.input_device ?? null;
+  useEffect(() => {
+    setCapabilities(null);
+    if (!selectedDevice) return;
+    getInputCapabilities(selectedDevice)
+      .then(setCapabilities)
+      .catch(() => setCapabilities(null));
+  }, [selectedDevice]);
+
   if (!localSettings) return null;
 
+  const captureConfig = localSettings.input_device
+    ? localSettings.device_configs[localSettings.input_device]
+    : undefined;
+
   const updateField = <K extends keyof AppSettings>(
     key: K,
     value: AppSettings[K]
@@ -40,22 +80,64 @@ export function SettingsPanel() {
   const updateProviderConfig = (
     providerId: string,
     field: string,
-    value: string
+    value: string | number
   ) => {
     setLocalSettings((prev) => {
       if (!prev) return prev;
       const configs = { ...prev.provider_configs };
       configs[providerId] = {
         ...configs[providerId],
-        [field]: value || null,
+        [field]: typeof value === "number" ? value : value || null,
       };
       return { ...prev, provider_configs: configs };
     });
   };
 
+  const updateCaptureConfig = (patch: Partial<CaptureConfig>) => {
+    setLocalSettings((prev) => {
+      if (!prev || !prev.input_device) return prev;
+      const current = prev.device_configs[prev.input_device] ?? {
+        sample_rate: null,
+        buffer_size: null,
+        channels: [],
+      };
+      return {
+        ...prev,
+        device_configs: {
+          ...prev.device_configs,
+          [prev.input_device]: { ...current, ...patch },
+        },
+      };
+    });
+  };
+
+  const toggleCaptureChannel = (channel: number, enabled: boolean) => {
+    const channels = captureConfig?.channels ?? [];
+    updateCaptureConfig({
+      channels: enabled
+        ? [...channels, channel].sort((a, b) => a - b)
+        : channels.filter((c) => c !== channel),
+    });
+  };
+
+  const handleTestDevice = async () => {
+    if (!localSettings) return;
+    setTestingDevice(true);
+    setDeviceReport(null);
+    setDeviceTestError(null);
+    try {
+      setDeviceReport(await testInputDevice(localSettings.input_device));
+    } catch (e) {
+      setDeviceTestError(String(e));
+    } finally {
+      setTestingDevice(false);
+    }
+  };
+
   const handleSave = async () => {
     if (!localSettings) return;
     setSaving(true);
+    setSaveError(null);
     try {
       await saveSettings(localSettings);
       setSettings(localSettings);
@@ -63,6 +145,7 @@ export function SettingsPanel() {
       setTimeout(() => setSaved(false), 2000);
     } catch (e) {
       console.error("Failed to save settings:", e);
+      setSaveError(String(e));
     }
     setSaving(false);
   };
@@ -86,38 +169,38 @@ export function SettingsPanel() {
       return;
     }
 
-    // Ignore standalone modifier keys
-    if (
-      ["Control", "Alt", "Shift", "Meta", "Command"].includes(e.key)
-    ) {
-      return;
-    }
+    const hotkey = hotkeyFromEvent(e);
+    if (!hotkey) return;
 
-    // Build the hotkey string
-    const parts: string[] = [];
+    updateField("hotkey", hotkey);
+    setIsRecordingHotkey(false);
+    hotkeyRef.current?.blur();
+  };
 
-    if (e.ctrlKey || e.metaKey) {
-      parts.push("CommandOrControl");
-    }
-    if (e.altKey) {
-      parts.push("Alt");
-    }
-    if (e.shiftKey) {
-      parts.push("Shift");
+  const handlePauseHotkeyKeyDown = (e: React.KeyboardEvent<HTMLDivElement>) => {
+    e.preventDefault();
+    e.stopPropagation();
+
+    if (e.key === "Escape") {
+      setIsRecordingPauseHotkey(false);
+      pauseHotkeyRef.current?.blur();
+      return;
     }
 
-    let key = e.key;
-    if (key === " ") {
-      key = "Space";
-    } else if (key.length === 1) {
-      key = key.toUpperCase();
+    // Backspace/Delete: remove the pause hotkey
+    if (e.key === "Backspace" || e.key === "Delete") {
+      updateField("pause_hotkey", null);
+      setIsRecordingPauseHotkey(false);
+      pauseHotkeyRef.current?.blur();
+      return;
     }
 
-    parts.push(key);
+    const hotkey = hotkeyFromEvent(e);
+    if (!hotkey) return;
 
-    updateField("hotkey", parts.join("+"));
-    setIsRecordingHotkey(false);
-    hotkeyRef.current?.blur();
+    updateField("pause_hotkey", hotkey);
+    setIsRecordingPauseHotkey(false);
+    pauseHotkeyRef.current?.blur();
   };
 
   const activeProviderConfig =
@@ -166,11 +249,173 @@ export function SettingsPanel() {
             </option>
           ))}
         </select>
+        {capabilities && (
+          <div className="flex flex-col gap-2 pl-3 border-l border-zinc-800">
+            <label className="flex items-center justify-between">
+              <span className="text-sm text-zinc-400">Sample rate</span>
+              <select
+                value={captureConfig?.sample_rate ?? ""}
+                onChange={(e) =>
+                  updateCaptureConfig({
+                    sample_rate: e.target.value ? Number(e.target.value) : null,
+                  })
+                }
+                className="bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+              >
+                <option value="">
+                  Default ({capabilities.default_sample_rate} Hz)
+                </option>
+                {capabilities.sample_rates.map((rate) => (
+                  <option key={rate} value={rate}>
+                    {rate} Hz
+                  </option>
+                ))}
+              </select>
+            </label>
+            <label className="flex items-center justify-between">
+              <span className="text-sm text-zinc-400">Buffer size (frames)</span>
+              <input
+                type="number"
+                min={capabilities.min_buffer_size ?? undefined}
+                max={capabilities.max_buffer_size ?? undefined}
+                placeholder="Default"
+                value={captureConfig?.buffer_size ?? ""}
+                onChange={(e) =>
+                  updateCaptureConfig({
+                    buffer_size: e.target.value ? Number(e.target.value) : null,
+                  })
+                }
+                className="w-24 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </label>
+            {capabilities.max_channels > 1 && (
+              <div className="flex items-center justify-between">
+                <span className="text-sm text-zinc-400">Channels</span>
+                <div className="flex flex-wrap gap-2">
+                  {Array.from({ length: capabilities.max_channels }, (_, i) => (
+                    <label key={i} className="flex items-center gap-1 text-xs text-zinc-300">
+                      <input
+                        type="checkbox"
+                        checked={captureConfig?.channels.includes(i) ?? false}
+                        onChange={(e) => toggleCaptureChannel(i, e.target.checked)}
+                        className="w-3 h-3 accent-blue-500"
+                      />
+                      {i + 1}
+                    </label>
+                  ))}
+                </div>
+              </div>
+            )}
+            <p className="text-xs text-zinc-500">
+              With no channels ticked, all channels are mixed to mono.
+            </p>
+          </div>
+        )}
+        <button
+          onClick={handleTestDevice}
+          disabled={testingDevice}
+          className="self-start px-3 py-1.5 bg-zinc-800 hover:bg-zinc-700 disabled:opacity-50 border border-zinc-700 rounded-lg text-xs text-zinc-300 transition-colors"
+        >
+          {testingDevice ? "Testing... speak now" : "Test Microphone"}
+        </button>
+        {deviceTestError && (
+          <p className="text-xs text-red-400">{deviceTestError}</p>
+        )}
+        {deviceReport && (
+          <div className="flex flex-col gap-1 bg-zinc-800/60 rounded-lg p-3 text-xs text-zinc-400">
+            <span>
+              {deviceReport.sample_rate} Hz &middot; {deviceReport.channels} ch
+              &middot; {deviceReport.sample_format}
+            </span>
+            <span>
+              Noise floor {deviceReport.noise_floor_db.toFixed(1)} dB &middot;
+              Peak {deviceReport.peak_db.toFixed(1)} dB &middot; SNR{" "}
+              {deviceReport.snr_db.toFixed(1)} dB
+            </span>
+            <span>
+              {deviceReport.clipped_samples} clipped samples &middot;{" "}
+              {deviceReport.dropouts} dropouts
+            </span>
+            <span
+              className={
+                deviceReport.suitable ? "text-green-400" : "text-amber-400"
+              }
+            >
+              {deviceReport.recommendation}
+            </span>
+          </div>
+        )}
+      </section>
+
+      {/* Resampling Quality */}
+      <section className="flex flex-col gap-2">
+        <label className="text-sm font-medium text-zinc-400">
+          Resampling Quality
+        </label>
+        <select
+          value={localSettings.resample_quality}
+          onChange={(e) =>
+            updateField("resample_quality", e.target.value as ResampleQuality)
+          }
+          className="bg-zinc-800 border border-zinc-700 rounded-lg px-3 py-2 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+        >
+          <option value="Fast">Fast (linear)</option>
+          <option value="Balanced">Balanced</option>
+          <option value="High">High</option>
+        </select>
+      </section>
+
+      {/* Audio Cleanup */}
+      <section className="flex flex-col gap-3">
+        <label className="text-sm font-medium text-zinc-400">
+          Audio Cleanup
+        </label>
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Remove rumble and hum</span>
+          <input
+            type="checkbox"
+            checked={localSettings.dsp.high_pass}
+            onChange={(e) =>
+              updateField("dsp", { ...localSettings.dsp, high_pass: e.target.checked })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Suppress background noise</span>
+          <input
+            type="checkbox"
+            checked={localSettings.dsp.noise_suppression}
+            onChange={(e) =>
+              updateField("dsp", {
+                ...localSettings.dsp,
+                noise_suppression: e.target.checked,
+              })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Normalize volume</span>
+          <input
+            type="checkbox"
+            checked={localSettings.dsp.normalize}
+            onChange={(e) =>
+              updateField("dsp", { ...localSettings.dsp, normalize: e.target.checked })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <p className="text-xs text-zinc-500">
+          Applied to the recording before it is transcribed. Noise
+          suppression helps with steady noise such as fans.
+        </p>
       </section>
 
       {/* API Key (for cloud providers) */}
       {(localSettings.active_provider === "OpenAiWhisper" ||
-        localSettings.active_provider === "GoogleCloud") && (
+        localSettings.active_provider === "GoogleCloud" ||
+        localSettings.active_provider === "OpenAiRealtime") && (
         <section className="flex flex-col gap-2">
           <label className="text-sm font-medium text-zinc-400">API Key</label>
           <input
@@ -189,6 +434,115 @@ export function SettingsPanel() {
         </section>
       )}
 
+      {/* Session Endpoint (for the realtime provider) */}
+      {localSettings.active_provider === "OpenAiRealtime" && (
+        <section className="flex flex-col gap-2">
+          <label className="text-sm font-medium text-zinc-400">Endpoint</label>
+          <input
+            type="text"
+            value={activeProviderConfig.endpoint || ""}
+            onChange={(e) =>
+              updateProviderConfig(
+                localSettings.active_provider,
+                "endpoint",
+                e.target.value
+              )
+            }
+            placeholder="wss://api.openai.com/v1/realtime?intent=transcription"
+            className="bg-zinc-800 border border-zinc-700 rounded-lg px-3 py-2 text-sm text-zinc-200 placeholder-zinc-500 focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+          <p className="text-xs text-zinc-500">
+            Audio is streamed while you speak, so the transcript is ready
+            soon after you stop. Turn on "Show words while speaking" below.
+          </p>
+        </section>
+      )}
+
+      {/* Upload Format (for cloud providers) */}
+      {(localSettings.active_provider === "OpenAiWhisper" ||
+        localSettings.active_provider === "GoogleCloud") && (
+        <section className="flex flex-col gap-2">
+          <label className="text-sm font-medium text-zinc-400">
+            Upload Format
+          </label>
+          <select
+            value={activeProviderConfig.encoding ?? "Wav"}
+            onChange={(e) =>
+              updateProviderConfig(
+                localSettings.active_provider,
+                "encoding",
+                e.target.value
+              )
+            }
+            className="bg-zinc-800 border border-zinc-700 rounded-lg px-3 py-2 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+          >
+            <option value="Wav">WAV (uncompressed)</option>
+            <option value="Flac">FLAC (lossless, smaller)</option>
+            {encodings.includes("OggOpus") && (
+              <option value="OggOpus">Ogg/Opus (lossy, smallest)</option>
+            )}
+          </select>
+        </section>
+      )}
+
+      {/* Alternatives (for Google Cloud) */}
+      {localSettings.active_provider === "GoogleCloud" && (
+        <section className="flex flex-col gap-2">
+          <label className="flex items-center justify-between">
+            <span className="text-sm text-zinc-300">Alternative readings</span>
+            <input
+              type="number"
+              min={0}
+              max={29}
+              step={1}
+              value={activeProviderConfig.max_alternatives ?? 3}
+              onChange={(e) =>
+                updateProviderConfig(
+                  localSettings.active_provider,
+                  "max_alternatives",
+                  Math.min(29, Math.max(0, Math.round(Number(e.target.value))))
+                )
+              }
+              className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+          <p className="text-xs text-zinc-500">
+            Other ways the audio could be read, offered when a transcription
+            is too uncertain to paste.
+          </p>
+        </section>
+      )}
+
+      {/* Long Recordings (for cloud providers) */}
+      {(localSettings.active_provider === "OpenAiWhisper" ||
+        localSettings.active_provider === "GoogleCloud") && (
+        <section className="flex flex-col gap-2">
+          <label className="flex items-center justify-between">
+            <span className="text-sm text-zinc-300">
+              Parallel requests for long recordings
+            </span>
+            <input
+              type="number"
+              min={1}
+              max={8}
+              step={1}
+              value={localSettings.chunking.max_parallel}
+              onChange={(e) =>
+                updateField("chunking", {
+                  ...localSettings.chunking,
+                  max_parallel: Math.max(1, Math.round(Number(e.target.value))),
+                })
+              }
+              className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+          <p className="text-xs text-zinc-500">
+            Recordings longer than the provider accepts are split at pauses
+            and sent in parts. More parallel requests finish sooner.
+          </p>
+        </section>
+      )}
+
       {/* Model Selection for Local Whisper */}
       {localSettings.active_provider === "LocalWhisper" && (
         <section className="flex flex-col gap-2">
@@ -272,6 +626,37 @@ export function SettingsPanel() {
         </p>
       </section>
 
+      {/* Pause Hotkey */}
+      <section className="flex flex-col gap-2">
+        <label className="text-sm font-medium text-zinc-400">Pause Hotkey</label>
+        <div
+          ref={pauseHotkeyRef}
+          tabIndex={0}
+          onFocus={() => setIsRecordingPauseHotkey(true)}
+          onBlur={() => setIsRecordingPauseHotkey(false)}
+          onKeyDown={handlePauseHotkeyKeyDown}
+          className={`flex items-center h-10 bg-zinc-800 border rounded-lg px-3 cursor-pointer transition-colors ${
+            isRecordingPauseHotkey
+              ? "border-blue-500 ring-2 ring-blue-500/30"
+              : "border-zinc-700 hover:border-zinc-600"
+          }`}
+        >
+          {isRecordingPauseHotkey ? (
+            <span className="text-sm text-zinc-400 animate-pulse">
+              Press a key combination...
+            </span>
+          ) : localSettings.pause_hotkey ? (
+            <HotkeyDisplay hotkey={localSettings.pause_hotkey} size="sm" />
+          ) : (
+            <span className="text-sm text-zinc-500">Not set</span>
+          )}
+        </div>
+        <p className="text-xs text-zinc-500">
+          Pauses and resumes a recording, which is transcribed as one when
+          stopped. Backspace to remove.
+        </p>
+      </section>
+
       {/* Language */}
       <section className="flex flex-col gap-2">
         <label className="text-sm font-medium text-zinc-400">Language</label>
@@ -294,6 +679,70 @@ export function SettingsPanel() {
         </select>
       </section>
 
+      {/* Folder Transcription */}
+      <section className="flex flex-col gap-3">
+        <label className="text-sm font-medium text-zinc-400">
+          Folder Transcription
+        </label>
+        <div className="flex items-center justify-between gap-3">
+          <span
+            className="text-sm text-zinc-300 truncate"
+            title={localSettings.batch.watch_folder ?? undefined}
+          >
+            {localSettings.batch.watch_folder ?? "No folder watched"}
+          </span>
+          <div className="flex gap-3 shrink-0">
+            <button
+              onClick={async () => {
+                const folder = await pickFolder();
+                if (folder) {
+                  updateField("batch", { ...localSettings.batch, watch_folder: folder });
+                }
+              }}
+              className="text-xs text-blue-400 hover:text-blue-300 transition-colors"
+            >
+              {localSettings.batch.watch_folder ? "Change" : "Watch Folder"}
+            </button>
+            {localSettings.batch.watch_folder && (
+              <button
+                onClick={() =>
+                  updateField("batch", { ...localSettings.batch, watch_folder: null })
+                }
+                className="text-xs text-zinc-500 hover:text-red-400 transition-colors"
+              >
+                Stop
+              </button>
+            )}
+          </div>
+        </div>
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Write .txt transcripts</span>
+          <input
+            type="checkbox"
+            checked={localSettings.batch.write_txt}
+            onChange={(e) =>
+              updateField("batch", { ...localSettings.batch, write_txt: e.target.checked })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Write .json transcripts</span>
+          <input
+            type="checkbox"
+            checked={localSettings.batch.write_json}
+            onChange={(e) =>
+              updateField("batch", { ...localSettings.batch, write_json: e.target.checked })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <p className="text-xs text-zinc-500">
+          New audio files in the watched folder are transcribed while the app
+          runs, with the transcripts saved next to them.
+        </p>
+      </section>
+
       {/* Toggles */}
       <section className="flex flex-col gap-3">
         <label className="flex items-center justify-between cursor-pointer">
@@ -305,6 +754,234 @@ export function SettingsPanel() {
             className="w-4 h-4 accent-blue-500"
           />
         </label>
+        {localSettings.auto_paste && (
+          <label className="flex items-center justify-between cursor-pointer">
+            <span className="text-sm text-zinc-400">
+              Ask before pasting uncertain transcriptions
+            </span>
+            <input
+              type="checkbox"
+              checked={localSettings.confidence.confirm_low}
+              onChange={(e) =>
+                updateField("confidence", {
+                  ...localSettings.confidence,
+                  confirm_low: e.target.checked,
+                })
+              }
+              className="w-4 h-4 accent-blue-500"
+            />
+          </label>
+        )}
+        {localSettings.auto_paste && localSettings.confidence.confirm_low && (
+          <label className="flex items-center justify-between">
+            <span className="text-sm text-zinc-400">Minimum confidence (%)</span>
+            <input
+              type="number"
+              min={0}
+              max={100}
+              step={5}
+              value={Math.round(localSettings.confidence.threshold * 100)}
+              onChange={(e) =>
+                updateField("confidence", {
+                  ...localSettings.confidence,
+                  threshold: Math.min(100, Math.max(0, Number(e.target.value))) / 100,
+                })
+              }
+              className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+        )}
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Show words while speaking</span>
+          <input
+            type="checkbox"
+            checked={localSettings.streaming.enabled}
+            onChange={(e) =>
+              updateField("streaming", {
+                ...localSettings.streaming,
+                enabled: e.target.checked,
+              })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        {localSettings.streaming.enabled && (
+          <p className="text-xs text-zinc-500">
+            Audio is transcribed as it is captured, without silence trimming
+            or audio cleanup. If nothing is heard, the recording is
+            transcribed again after it stops.
+          </p>
+        )}
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">Trim silence before upload</span>
+          <input
+            type="checkbox"
+            checked={localSettings.silence_trim.enabled}
+            onChange={(e) =>
+              updateField("silence_trim", {
+                ...localSettings.silence_trim,
+                enabled: e.target.checked,
+              })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <label className="flex items-center justify-between cursor-pointer">
+          <span className="text-sm text-zinc-300">
+            Stop automatically after silence (Toggle mode)
+          </span>
+          <input
+            type="checkbox"
+            checked={localSettings.vad_auto_stop}
+            onChange={(e) => updateField("vad_auto_stop", e.target.checked)}
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        {localSettings.vad_auto_stop && (
+          <label className="flex items-center justify-between">
+            <span className="text-sm text-zinc-400">Silence timeout (seconds)</span>
+            <input
+              type="number"
+              min={0.5}
+              step={0.5}
+              value={localSettings.vad.silence_timeout_ms / 1000}
+              onChange={(e) =>
+                updateField("vad", {
+                  ...localSettings.vad,
+                  silence_timeout_ms: Math.round(Number(e.target.value) * 1000),
+                })
+              }
+              className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+        )}
+        <label className="flex items-center justify-between">
+          <span className="text-sm text-zinc-300">
+            Stop recordings automatically after a maximum length
+          </span>
+          <input
+            type="checkbox"
+            checked={localSettings.max_duration.enabled}
+            onChange={(e) =>
+              updateField("max_duration", {
+                ...localSettings.max_duration,
+                enabled: e.target.checked,
+              })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        {localSettings.max_duration.enabled && (
+          <label className="flex items-center justify-between">
+            <span className="text-sm text-zinc-400">Maximum length (minutes)</span>
+            <input
+              type="number"
+              min={1}
+              step={1}
+              value={localSettings.max_duration.max_secs / 60}
+              onChange={(e) =>
+                updateField("max_duration", {
+                  ...localSettings.max_duration,
+                  max_secs: Math.round(Number(e.target.value) * 60),
+                })
+              }
+              className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+          </label>
+        )}
+        <label className="flex items-center justify-between">
+          <span className="text-sm text-zinc-300">
+            Switch to the default microphone if the device disconnects
+          </span>
+          <input
+            type="checkbox"
+            checked={localSettings.device_failover}
+            onChange={(e) => updateField("device_failover", e.target.checked)}
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        <label className="flex items-center justify-between">
+          <span className="text-sm text-zinc-300">
+            Keep microphone open for pre-roll
+          </span>
+          <input
+            type="checkbox"
+            checked={localSettings.preroll.enabled}
+            onChange={(e) =>
+              updateField("preroll", {
+                ...localSettings.preroll,
+                enabled: e.target.checked,
+              })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        {localSettings.preroll.enabled && (
+          <>
+            <label className="flex items-center justify-between">
+              <span className="text-sm text-zinc-400">Pre-roll (ms)</span>
+              <input
+                type="number"
+                min={0}
+                max={2000}
+                step={100}
+                value={localSettings.preroll.duration_ms}
+                onChange={(e) =>
+                  updateField("preroll", {
+                    ...localSettings.preroll,
+                    duration_ms: Number(e.target.value),
+                  })
+                }
+                className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </label>
+            <p className="text-xs text-zinc-500">
+              The microphone stays open while the app runs so the first word
+              is never clipped. Audio before the hotkey is kept in memory
+              only and discarded unless you start recording.
+            </p>
+          </>
+        )}
+        <label className="flex items-center justify-between">
+          <span className="text-sm text-zinc-300">
+            Move long recordings to disk
+          </span>
+          <input
+            type="checkbox"
+            checked={localSettings.spill.enabled}
+            onChange={(e) =>
+              updateField("spill", {
+                ...localSettings.spill,
+                enabled: e.target.checked,
+              })
+            }
+            className="w-4 h-4 accent-blue-500"
+          />
+        </label>
+        {localSettings.spill.enabled && (
+          <>
+            <label className="flex items-center justify-between">
+              <span className="text-sm text-zinc-400">After (minutes)</span>
+              <input
+                type="number"
+                min={1}
+                step={1}
+                value={localSettings.spill.threshold_secs / 60}
+                onChange={(e) =>
+                  updateField("spill", {
+                    ...localSettings.spill,
+                    threshold_secs: Math.round(Number(e.target.value) * 60),
+                  })
+                }
+                className="w-20 bg-zinc-800 border border-zinc-700 rounded-lg px-2 py-1 text-sm text-zinc-200 focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </label>
+            <p className="text-xs text-zinc-500">
+              Keeps memory use flat during hour-long recordings. If the app
+              closes mid-recording, the audio can be recovered from History.
+            </p>
+          </>
+        )}
       </section>
 
       {/* Save Button */}
@@ -315,6 +992,37 @@ export function SettingsPanel() {
       >
         {saving ? "Saving..." : saved ? "Saved!" : "Save Settings"}
       </button>
+      {saveError && <p className="text-xs text-red-400">{saveError}</p>}
     </div>
   );
 }
+
+/** Build a hotkey string such as "CommandOrControl+Shift+Space" from a key
+ * press, or null while only modifiers are held. */
+function hotkeyFromEvent(e: React.KeyboardEvent): string | null {
+  if (["Control", "Alt", "Shift", "Meta", "Command"].includes(e.key)) {
+    return null;
+  }
+
+  const parts: string[] = [];
+
+  if (e.ctrlKey || e.metaKey) {
+    parts.push("CommandOrControl");
+  }
+  if (e.altKey) {
+    parts.push("Alt");
+  }
+  if (e.shiftKey) {
+    parts.push("Shift");
+  }
+
+  let key = e.key;
+  if (key === " ") {
+    key = "Space";
+  } else if (key.length === 1) {
+    key = key.toUpperCase();
+  }
+
+  parts.push(key);
+  return parts.join("+");
+}
@@ -1,7 +1,12 @@
 import type {
   AppSettings,
   AudioDevice,
+  AudioEncoding,
+  BatchProgress,
+  DeviceCapabilities,
+  DeviceTestReport,
   ProviderInfo,
+  RecoveredRecording,
   TranscriptionEntry,
 } from "../types";
 
@@ -19,12 +24,72 @@ const defaultSettings: AppSettings = {
   active_provider: "OpenAiWhisper",
   interaction_mode: "Toggle",
   hotkey: DEFAULT_HOTKEY,
+  pause_hotkey: null,
   language: "auto",
   provider_configs: {},
   local_whisper_model_path: null,
   auto_paste: true,
   show_overlay: true,
   input_device: null,
+  device_failover: true,
+  device_configs: {},
+  vad_auto_stop: false,
+  vad: {
+    energy_threshold: 0.01,
+    zcr_threshold: 0.25,
+    silence_timeout_ms: 2000,
+    min_speech_ms: 200,
+    frame_ms: 30,
+  },
+  max_duration: {
+    enabled: true,
+    max_secs: 7200,
+    warning_secs: 60,
+  },
+  dsp: {
+    high_pass: true,
+    high_pass_hz: 80,
+    noise_suppression: false,
+    noise_suppression_strength: 1.5,
+    normalize: true,
+    target_dbfs: -20,
+    max_gain_db: 24,
+  },
+  silence_trim: {
+    enabled: true,
+    threshold: 0.01,
+    padding_ms: 200,
+    max_pause_ms: 700,
+    frame_ms: 10,
+  },
+  resample_quality: "Balanced",
+  preroll: {
+    enabled: false,
+    duration_ms: 500,
+  },
+  spill: {
+    enabled: true,
+    threshold_secs: 300,
+  },
+  chunking: {
+    max_parallel: 1,
+    overlap_ms: 1000,
+  },
+  streaming: {
+    enabled: false,
+    interim_interval_ms: 0,
+    pause_ms: 600,
+  },
+  confidence: {
+    confirm_low: false,
+    threshold: 0.6,
+  },
+  batch: {
+    write_txt: true,
+    write_json: true,
+    watch_folder: null,
+    poll_secs: 10,
+  },
 };
 
 const defaultProviders: ProviderInfo[] = [
@@ -32,6 +97,7 @@ const defaultProviders: ProviderInfo[] = [
   { id: "GoogleCloud", name: "Google Cloud Speech-to-Text", available: true },
   { id: "LocalWhisper", name: "Local Whisper (whisper.cpp)", available: false },
   { id: "NativeStt", name: "Native OS Speech-to-Text", available: true },
+  { id: "OpenAiRealtime", name: "OpenAI Realtime", available: true },
 ];
 
 export async function startRecording(): Promise<void> {
@@ -42,6 +108,14 @@ export async function stopRecordingAndTranscribe(): Promise<string> {
   return tauriInvoke("stop_recording_and_transcribe");
 }
 
+export async function pauseRecording(): Promise<void> {
+  return tauriInvoke("pause_recording");
+}
+
+export async function resumeRecording(): Promise<void> {
+  return tauriInvoke("resume_recording");
+}
+
 export async function getRecordingState(): Promise<boolean> {
   return tauriInvoke<boolean>("get_recording_state").catch(() => false);
 }
@@ -62,6 +136,20 @@ export async function clearHistory(): Promise<void> {
   return tauriInvoke("clear_history");
 }
 
+export async function copyToClipboard(text: string): Promise<void> {
+  return tauriInvoke("copy_to_clipboard", { text });
+}
+
+/** Hide the window and paste a reading held back from auto-paste. */
+export async function pasteConfirmed(text: string): Promise<void> {
+  return tauriInvoke("paste_confirmed", { text });
+}
+
+/** Upload formats this build can encode; Ogg/Opus is an optional feature. */
+export async function getAudioEncodings(): Promise<AudioEncoding[]> {
+  return tauriInvoke<AudioEncoding[]>("get_audio_encodings").catch(() => ["Wav", "Flac"]);
+}
+
 export async function getProviders(): Promise<ProviderInfo[]> {
   return tauriInvoke<ProviderInfo[]>("get_providers").catch(() => defaultProviders);
 }
@@ -69,3 +157,66 @@ export async function getProviders(): Promise<ProviderInfo[]> {
 export async function listInputDevices(): Promise<AudioDevice[]> {
   return tauriInvoke<AudioDevice[]>("list_input_devices").catch(() => []);
 }
+
+export async function getInputCapabilities(
+  deviceName: string | null
+): Promise<DeviceCapabilities> {
+  return tauriInvoke<DeviceCapabilities>("get_input_capabilities", { deviceName });
+}
+
+export async function testInputDevice(
+  deviceName: string | null,
+  durationMs?: number
+): Promise<DeviceTestReport> {
+  return tauriInvoke<DeviceTestReport>("test_input_device", {
+    deviceName,
+    durationMs,
+  });
+}
+
+export async function listRecoveredRecordings(): Promise<RecoveredRecording[]> {
+  return tauriInvoke<RecoveredRecording[]>("list_recovered_recordings").catch(() => []);
+}
+
+export async function transcribeRecoveredRecording(name: string): Promise<string> {
+  return tauriInvoke("transcribe_recovered_recording", { name });
+}
+
+export async function transcribeFile(path: string): Promise<string> {
+  return tauriInvoke("transcribe_file", { path });
+}
+
+export async function startBatch(folder: string): Promise<void> {
+  return tauriInvoke("start_batch", { folder });
+}
+
+export async function cancelBatch(): Promise<void> {
+  return tauriInvoke("cancel_batch");
+}
+
+export async function getBatchStatus(): Promise<BatchProgress[]> {
+  if (!isTauri) return [];
+  return tauriInvoke("get_batch_status");
+}
+
+/** Ask for a folder; null if the dialog was cancelled. */
+export async function pickFolder(): Promise<string | null> {
+  if (!isTauri) throw new Error("Not running in Tauri");
+  const { open } = await import("@tauri-apps/plugin-dialog");
+  return open({ multiple: false, directory: true });
+}
+
+/** Ask for an audio file to transcribe; null if the dialog was cancelled. */
+export async function pickAudioFile(): Promise<string | null> {
+  if (!isTauri) throw new Error("Not running in Tauri");
+  const { open } = await import("@tauri-apps/plugin-dialog");
+  return open({
+    multiple: false,
+    directory: false,
+    filters: [{ name: "Audio", extensions: ["wav", "flac", "mp3", "ogg", "oga", "opus"] }],
+  });
+}
+
+export async function discardRecoveredRecording(name: string): Promise<void> {
+  return tauriInvoke("discard_recovered_recording", { name });
+}
@@ -1,10 +1,27 @@
-import type { TranscriptionResult } from "../types";
+import type {
+  AudioDevice,
+  AudioLevel,
+  BatchProgress,
+  Hypothesis,
+  MaxDurationWarning,
+  StreamErrorEvent,
+  TranscriptionResult,
+} from "../types";
 
 export interface EventCallbacks {
   onRecordingStarted: () => void;
   onRecordingStopped: () => void;
+  onRecordingPaused: () => void;
+  onRecordingResumed: () => void;
+  onAudioLevel: (level: AudioLevel) => void;
+  onMaxDurationWarning: (warning: MaxDurationWarning) => void;
+  onStreamError: (event: StreamErrorEvent) => void;
+  onInputDevicesChanged: (devices: AudioDevice[]) => void;
   onTranscribing: () => void;
+  onPartialTranscription: (hypothesis: Hypothesis) => void;
   onTranscriptionComplete: (result: TranscriptionResult) => void;
+  onLowConfidence: (result: TranscriptionResult) => void;
+  onBatchProgress: (progress: BatchProgress) => void;
   onError: (error: string) => void;
 }
 
@@ -32,18 +49,72 @@ export async function setupEventListeners(
     })
   );
 
+  unlisteners.push(
+    await listen("recording-paused", () => {
+      callbacks.onRecordingPaused();
+    })
+  );
+
+  unlisteners.push(
+    await listen("recording-resumed", () => {
+      callbacks.onRecordingResumed();
+    })
+  );
+
+  unlisteners.push(
+    await listen<AudioLevel>("audio-level", (event) => {
+      callbacks.onAudioLevel(event.payload);
+    })
+  );
+
+  unlisteners.push(
+    await listen<MaxDurationWarning>("max-duration-warning", (event) => {
+      callbacks.onMaxDurationWarning(event.payload);
+    })
+  );
+
+  unlisteners.push(
+    await listen<BatchProgress>("batch-progress", (event) => {
+      callbacks.onBatchProgress(event.payload);
+    })
+  );
+
+  unlisteners.push(
+    await listen<StreamErrorEvent>("stream-error", (event) => {
+      callbacks.onStreamError(event.payload);
+    })
+  );
+
+  unlisteners.push(
+    await listen<AudioDevice[]>("input-devices-changed", (event) => {
+      callbacks.onInputDevicesChanged(event.payload);
+    })
+  );
+
   unlisteners.push(
     await listen("transcribing", () => {
       callbacks.onTranscribing();
     })
   );
 
+  unlisteners.push(
+    await listen<Hypothesis>("partial-transcription", (event) => {
+      callbacks.onPartialTranscription(event.payload);
+    })
+  );
+
   unlisteners.push(
     await listen<TranscriptionResult>("transcription-complete", (event) => {
       callbacks.onTranscriptionComplete(event.payload);
     })
   );
 
+  unlisteners.push(
+    await listen<TranscriptionResult>("low-confidence", (event) => {
+      callbacks.onLowConfidence(event.payload);
+    })
+  );
+
   unlisteners.push(
     await listen<string>("error", (event) => {
       callbacks.onError(event.payload);
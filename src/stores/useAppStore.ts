@@ -1,20 +1,35 @@
 import { create } from "zustand";
 import type {
   AppSettings,
+  AudioDevice,
+  AudioLevel,
+  BatchProgress,
+  Hypothesis,
+  MaxDurationWarning,
   TranscriptionEntry,
   ProviderInfo,
+  TranscriptionResult,
 } from "../types";
 
 interface AppState {
   // Recording state
   isRecording: boolean;
+  isPaused: boolean;
   isTranscribing: boolean;
+  audioLevel: AudioLevel | null;
+  maxDurationWarning: MaxDurationWarning | null;
 
   // Data
   settings: AppSettings | null;
   history: TranscriptionEntry[];
   providers: ProviderInfo[];
+  inputDevices: AudioDevice[];
   currentTranscription: string;
+  /** Text recognised so far while recording: settled and still changing. */
+  liveTranscription: { final: string; interim: string };
+  /** A transcription held back from auto-paste for low confidence. */
+  pendingConfirmation: TranscriptionResult | null;
+  batchJobs: BatchProgress[];
   error: string | null;
 
   // View
@@ -22,28 +37,47 @@ interface AppState {
 
   // Actions
   setRecording: (val: boolean) => void;
+  setPaused: (val: boolean) => void;
   setTranscribing: (val: boolean) => void;
+  setAudioLevel: (level: AudioLevel | null) => void;
+  setMaxDurationWarning: (warning: MaxDurationWarning | null) => void;
   setSettings: (s: AppSettings) => void;
   setHistory: (entries: TranscriptionEntry[]) => void;
   addHistory: (entry: TranscriptionEntry) => void;
   setProviders: (providers: ProviderInfo[]) => void;
+  setInputDevices: (devices: AudioDevice[]) => void;
   setCurrentTranscription: (text: string) => void;
+  addHypothesis: (hypothesis: Hypothesis) => void;
+  clearLiveTranscription: () => void;
+  setPendingConfirmation: (result: TranscriptionResult | null) => void;
+  setBatchJobs: (jobs: BatchProgress[]) => void;
+  updateBatchJob: (progress: BatchProgress) => void;
   setError: (error: string | null) => void;
   setActiveView: (view: "home" | "settings" | "history") => void;
 }
 
 export const useAppStore = create<AppState>((set) => ({
   isRecording: false,
+  isPaused: false,
   isTranscribing: false,
+  audioLevel: null,
+  maxDurationWarning: null,
   settings: null,
   history: [],
   providers: [],
+  inputDevices: [],
   currentTranscription: "",
+  liveTranscription: { final: "", interim: "" },
+  pendingConfirmation: null,
+  batchJobs: [],
   error: null,
   activeView: "home",
 
   setRecording: (val) => set({ isRecording: val }),
+  setPaused: (val) => set({ isPaused: val }),
   setTranscribing: (val) => set({ isTranscribing: val }),
+  setAudioLevel: (level) => set({ audioLevel: level }),
+  setMaxDurationWarning: (warning) => set({ maxDurationWarning: warning }),
   setSettings: (s) => set({ settings: s }),
   setHistory: (entries) => set({ history: entries }),
   addHistory: (entry) =>
@@ -51,7 +85,27 @@ export const useAppStore = create<AppState>((set) => ({
       history: [entry, ...state.history].slice(0, 100),
     })),
   setProviders: (providers) => set({ providers }),
+  setInputDevices: (devices) => set({ inputDevices: devices }),
   setCurrentTranscription: (text) => set({ currentTranscription: text }),
+  addHypothesis: (hypothesis) =>
+    set((state) => {
+      const { final } = state.liveTranscription;
+      if (!hypothesis.is_final) {
+        return { liveTranscription: { final, interim: hypothesis.text.trim() } };
+      }
+      const text = [final, hypothesis.text.trim()].filter(Boolean).join(" ");
+      return { liveTranscription: { final: text, interim: "" } };
+    }),
+  clearLiveTranscription: () => set({ liveTranscription: { final: "", interim: "" } }),
+  setPendingConfirmation: (result) => set({ pendingConfirmation: result }),
+  setBatchJobs: (jobs) => set({ batchJobs: jobs }),
+  updateBatchJob: (progress) =>
+    set((state) => ({
+      batchJobs: [
+        ...state.batchJobs.filter((job) => job.watching !== progress.watching),
+        progress,
+      ].sort((a, b) => Number(a.watching) - Number(b.watching)),
+    })),
   setError: (error) => set({ error }),
   setActiveView: (view) => set({ activeView: view }),
 }));
@@ -2,27 +2,185 @@ export type ProviderId =
   | "OpenAiWhisper"
   | "GoogleCloud"
   | "LocalWhisper"
-  | "NativeStt";
+  | "NativeStt"
+  | "OpenAiRealtime";
 
 export interface ProviderConfig {
   api_key: string | null;
   model: string | null;
   language: string | null;
   endpoint: string | null;
+  encoding?: AudioEncoding;
+  max_alternatives?: number;
 }
 
+export type AudioEncoding = "Wav" | "Flac" | "OggOpus";
+
 export type InteractionMode = "PushToTalk" | "Toggle";
 
+export interface VadConfig {
+  energy_threshold: number;
+  zcr_threshold: number;
+  silence_timeout_ms: number;
+  min_speech_ms: number;
+  frame_ms: number;
+}
+
+export interface MaxDurationConfig {
+  enabled: boolean;
+  max_secs: number;
+  warning_secs: number;
+}
+
+export interface MaxDurationWarning {
+  remaining_ms: number;
+  max_ms: number;
+}
+
+export type ResampleQuality = "Fast" | "Balanced" | "High";
+
+export interface SilenceTrimConfig {
+  enabled: boolean;
+  threshold: number;
+  padding_ms: number;
+  max_pause_ms: number;
+  frame_ms: number;
+}
+
+export interface DspConfig {
+  high_pass: boolean;
+  high_pass_hz: number;
+  noise_suppression: boolean;
+  noise_suppression_strength: number;
+  normalize: boolean;
+  target_dbfs: number;
+  max_gain_db: number;
+}
+
+export interface StreamingConfig {
+  enabled: boolean;
+  interim_interval_ms: number;
+  pause_ms: number;
+}
+
+export interface ConfidenceConfig {
+  confirm_low: boolean;
+  threshold: number;
+}
+
+/** Text recognised since the last final hypothesis. */
+export interface Hypothesis {
+  text: string;
+  is_final: boolean;
+}
+
+export interface BatchConfig {
+  write_txt: boolean;
+  write_json: boolean;
+  watch_folder: string | null;
+  poll_secs: number;
+}
+
+export interface BatchProgress {
+  folder: string;
+  watching: boolean;
+  total: number;
+  done: number;
+  failed: number;
+  current: string | null;
+  last_error: string | null;
+  finished: boolean;
+}
+
+export interface PreRollConfig {
+  enabled: boolean;
+  duration_ms: number;
+}
+
+export interface SpillConfig {
+  enabled: boolean;
+  threshold_secs: number;
+}
+
+export interface ChunkingConfig {
+  max_parallel: number;
+  overlap_ms: number;
+}
+
+export interface CaptureConfig {
+  sample_rate: number | null;
+  buffer_size: number | null;
+  channels: number[];
+}
+
 export interface AppSettings {
   active_provider: ProviderId;
   interaction_mode: InteractionMode;
   hotkey: string;
+  pause_hotkey: string | null;
   language: string;
   provider_configs: Record<string, ProviderConfig>;
   local_whisper_model_path: string | null;
   auto_paste: boolean;
   show_overlay: boolean;
   input_device: string | null;
+  device_failover: boolean;
+  device_configs: Record<string, CaptureConfig>;
+  vad_auto_stop: boolean;
+  vad: VadConfig;
+  max_duration: MaxDurationConfig;
+  dsp: DspConfig;
+  silence_trim: SilenceTrimConfig;
+  resample_quality: ResampleQuality;
+  preroll: PreRollConfig;
+  spill: SpillConfig;
+  chunking: ChunkingConfig;
+  streaming: StreamingConfig;
+  confidence: ConfidenceConfig;
+  batch: BatchConfig;
+}
+
+export interface DeviceCapabilities {
+  default_sample_rate: number;
+  default_channels: number;
+  max_channels: number;
+  sample_rates: number[];
+  min_buffer_size: number | null;
+  max_buffer_size: number | null;
+}
+
+export interface DeviceTestReport {
+  device_name: string;
+  sample_rate: number;
+  channels: number;
+  sample_format: string;
+  duration_ms: number;
+  noise_floor_db: number;
+  peak_db: number;
+  clipped_samples: number;
+  snr_db: number;
+  dropouts: number;
+  suitable: boolean;
+  recommendation: string;
+}
+
+export interface StreamErrorEvent {
+  message: string;
+  device_lost: boolean;
+  recovered_on: string | null;
+  interrupted: boolean;
+}
+
+export interface AudioLevel {
+  rms: number;
+  peak: number;
+  clipping: boolean;
+}
+
+export interface RecoveredRecording {
+  name: string;
+  duration_ms: number;
+  recorded_at: string;
 }
 
 export interface AudioDevice {
@@ -30,11 +188,37 @@ export interface AudioDevice {
   is_default: boolean;
 }
 
+/** Times are milliseconds from the start of the recording. */
+export interface Segment {
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  confidence: number | null;
+}
+
+export interface Word {
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  confidence: number | null;
+}
+
+/** Another reading of the audio than the transcript. */
+export interface Alternative {
+  text: string;
+  confidence: number | null;
+}
+
 export interface TranscriptionResult {
   text: string;
   provider: ProviderId;
   duration_ms: number;
   language: string | null;
+  trimmed_ms: number;
+  segments: Segment[];
+  words: Word[];
+  confidence: number | null;
+  alternatives: Alternative[];
 }
 
 export interface TranscriptionEntry {
@@ -44,6 +228,10 @@ export interface TranscriptionEntry {
   timestamp: string;
   duration_ms: number;
   language: string | null;
+  trimmed_ms: number;
+  segments: Segment[];
+  words: Word[];
+  confidence: number | null;
 }
 
 export interface ProviderInfo {